@@ -0,0 +1,107 @@
+//! Compares the scalar `Mat4` and `Vec4` code with whatever the operators compile to.
+//! Run with `cargo bench --features simd` to compare against the SIMD implementations.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use wgpu_voxel_game::math::{Mat4, Vec3, Vec4};
+
+fn matrices() -> (Mat4, Mat4) {
+    let a = Mat4::transformation(
+        Vec3::new(1.0, 2.0, 3.0),
+        Vec3::new(0.3, 1.2, -0.7),
+        Vec3::new(1.0, 2.0, 0.5),
+    );
+    let b = Mat4::projection(70f32.to_radians(), 16.0 / 9.0, 0.01, 1000.0);
+    (a, b)
+}
+
+fn mat4_mul(c: &mut Criterion) {
+    let (a, b) = matrices();
+    let mut group = c.benchmark_group("mat4 * mat4");
+    group.bench_function("scalar", |bench| {
+        bench.iter(|| black_box(a).mul_scalar(&black_box(b)))
+    });
+    group.bench_function("operator", |bench| {
+        bench.iter(|| black_box(a) * black_box(b))
+    });
+    group.finish();
+}
+
+fn mat4_mul_vec4(c: &mut Criterion) {
+    let (a, _) = matrices();
+    let v = Vec4::new(1.0, -2.0, 3.0, 1.0);
+    let mut group = c.benchmark_group("mat4 * vec4");
+    group.bench_function("scalar", |bench| {
+        bench.iter(|| black_box(a).mul_vec4_scalar(black_box(v)))
+    });
+    group.bench_function("operator", |bench| {
+        bench.iter(|| black_box(a) * black_box(v))
+    });
+    group.finish();
+}
+
+fn mat4_transpose(c: &mut Criterion) {
+    let (a, _) = matrices();
+    let mut group = c.benchmark_group("mat4 transpose");
+    group.bench_function("scalar", |bench| {
+        bench.iter(|| black_box(a).transpose_scalar())
+    });
+    group.bench_function("operator", |bench| bench.iter(|| black_box(a).transpose()));
+    group.finish();
+}
+
+fn vec4_ops(c: &mut Criterion) {
+    let a = Vec4::new(1.0, -2.0, 3.0, 1.0);
+    let b = Vec4::new(0.5, 4.0, -1.5, 2.0);
+    let mut group = c.benchmark_group("vec4 add, mul and dot");
+    group.bench_function("scalar", |bench| {
+        bench.iter(|| {
+            let (a, b) = (black_box(a), black_box(b));
+            a.add_scalar(b).mul_scalar(b).dot_scalar(a)
+        })
+    });
+    group.bench_function("operator", |bench| {
+        bench.iter(|| {
+            let (a, b) = (black_box(a), black_box(b));
+            ((a + b) * b).dot(a)
+        })
+    });
+    group.finish();
+}
+
+/// Transforms the 8 corners of many boxes, like frustum culling chunks does.
+fn transform_corners(c: &mut Criterion) {
+    let (a, b) = matrices();
+    let m = b * a;
+    let corners: Vec<Vec4<f32>> = (0..4096)
+        .map(|i| {
+            let i = i as f32;
+            Vec4::new(i % 16.0, (i / 16.0) % 16.0, i / 256.0, 1.0)
+        })
+        .collect();
+    let mut group = c.benchmark_group("transform 4096 points");
+    group.bench_function("scalar", |bench| {
+        bench.iter(|| {
+            let m = black_box(m);
+            corners
+                .iter()
+                .fold(Vec4::all(0.0), |acc, v| acc + m.mul_vec4_scalar(*v))
+        })
+    });
+    group.bench_function("operator", |bench| {
+        bench.iter(|| {
+            let m = black_box(m);
+            corners.iter().fold(Vec4::all(0.0), |acc, v| acc + m * *v)
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    mat4_mul,
+    mat4_mul_vec4,
+    mat4_transpose,
+    vec4_ops,
+    transform_corners
+);
+criterion_main!(benches);
@@ -0,0 +1,201 @@
+// Vertex shader
+
+// A packed vertex, see `mesher::PackedVertex` for the layout.
+struct VertexInput {
+	@location(0) data: vec2<u32>,
+}
+struct VertexOutput {
+	@builtin(position) clip_position: vec4<f32>,
+	@location(0) tex_coords: vec2<f32>,
+	@location(1) normal: vec3<f32>,
+	// how much ambient light reaches this point, 0..1
+	@location(2) ao: f32,
+	// the brightness of the sky and block light, 0..1
+	@location(3) sky_light: f32,
+	@location(4) block_light: f32,
+	// how far the vertex is from the camera, for fog
+	@location(5) view_dist: f32,
+}
+
+@group(1) @binding(0)
+var<uniform> view_mat: mat4x4<f32>;
+
+@group(1) @binding(1)
+var<uniform> proj_mat: mat4x4<f32>;
+
+// the world position of the chunk being drawn, w is unused
+@group(2) @binding(0)
+var<uniform> chunk_origin: vec4<f32>;
+
+// the number of tiles along each side of the block atlas, `block::ATLAS_SIZE`
+let ATLAS_SIZE: u32 = 16u;
+
+// in the order of `model::Face`
+fn face_normal(face: u32) -> vec3<f32> {
+	switch face {
+		case 0u: { return vec3<f32>(1.0, 0.0, 0.0); }
+		case 1u: { return vec3<f32>(-1.0, 0.0, 0.0); }
+		case 2u: { return vec3<f32>(0.0, 1.0, 0.0); }
+		case 3u: { return vec3<f32>(0.0, -1.0, 0.0); }
+		case 4u: { return vec3<f32>(0.0, 0.0, 1.0); }
+		default: { return vec3<f32>(0.0, 0.0, -1.0); }
+	}
+}
+
+// `model::FACE_TEX_COORDS`
+fn corner_tex_coords(corner: u32) -> vec2<f32> {
+	switch corner {
+		case 0u: { return vec2<f32>(1.0, 0.0); }
+		case 1u: { return vec2<f32>(0.0, 0.0); }
+		case 2u: { return vec2<f32>(0.0, 1.0); }
+		default: { return vec2<f32>(1.0, 1.0); }
+	}
+}
+
+// the brightness of each ambient occlusion level, so a fully occluded corner isn't black
+fn ao_brightness(ao: u32) -> f32 {
+	switch ao {
+		case 0u: { return 0.4; }
+		case 1u: { return 0.6; }
+		case 2u: { return 0.8; }
+		default: { return 1.0; }
+	}
+}
+
+// the brightness of a light level, 0..=15.
+// Each level is a fixed fraction darker than the one above, which looks like an even falloff.
+fn light_brightness(level: u32) -> f32 {
+	return pow(0.8, f32(15u - level));
+}
+
+// A `PackedVertex`, unpacked.
+struct ChunkVertex {
+	// in world space
+	pos: vec3<f32>,
+	face: u32,
+	corner: u32,
+	texture: u32,
+	ao: u32,
+	sky_light: u32,
+	block_light: u32,
+	// how far below the top of its block the vertex is, in 16ths of a block
+	lowered: u32,
+}
+
+fn unpack_vertex(data: vec2<u32>) -> ChunkVertex {
+	var v: ChunkVertex;
+	let local_pos = vec3<u32>(data.x & 31u, (data.x >> 5u) & 31u, (data.x >> 10u) & 31u);
+	v.face = (data.x >> 15u) & 7u;
+	v.corner = (data.x >> 18u) & 3u;
+	v.texture = data.x >> 20u;
+	v.ao = data.y & 3u;
+	v.sky_light = (data.y >> 2u) & 15u;
+	v.block_light = (data.y >> 6u) & 15u;
+	// fluid surfaces sit a little lower than the top of their block
+	v.lowered = (data.y >> 10u) & 15u;
+
+	v.pos = chunk_origin.xyz + vec3<f32>(local_pos);
+	v.pos.y = v.pos.y - f32(v.lowered) / 16.0;
+	return v;
+}
+
+// the position of the top left corner of a tile in the block atlas, in tiles
+fn tile_pos(texture: u32) -> vec2<f32> {
+	return vec2<f32>(f32(texture % ATLAS_SIZE), f32(texture / ATLAS_SIZE));
+}
+
+fn vertex_output(v: ChunkVertex) -> VertexOutput {
+	var out: VertexOutput;
+
+	let view_pos = view_mat * vec4<f32>(v.pos, 1.0);
+	out.clip_position = proj_mat * view_pos;
+	out.view_dist = length(view_pos.xyz);
+
+	out.tex_coords = (tile_pos(v.texture) + corner_tex_coords(v.corner)) / f32(ATLAS_SIZE);
+	out.normal = face_normal(v.face);
+	out.ao = ao_brightness(v.ao);
+	out.sky_light = light_brightness(v.sky_light);
+	out.block_light = light_brightness(v.block_light);
+
+	return out;
+}
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+	return vertex_output(unpack_vertex(in.data));
+}
+
+// Fragment shader
+
+struct Lighting {
+	// points towards the sun
+	sun_dir: vec3<f32>,
+	ambient: f32,
+	sun_color: vec3<f32>,
+	// how bright sky light is, lower at night
+	sky_light: f32,
+}
+
+@group(0) @binding(0)
+var texture: texture_2d<f32>;
+@group(0) @binding(1)
+var texture_s: sampler;
+
+struct Fog {
+	color: vec3<f32>,
+	// above 0 for exponential fog, otherwise it's linear between `start` and `end`
+	density: f32,
+	start: f32,
+	end: f32,
+}
+
+@group(1) @binding(2)
+var<uniform> lighting: Lighting;
+
+@group(1) @binding(3)
+var<uniform> fog: Fog;
+
+// how much of a point `dist` away is hidden by the fog, 0..1. The same as `Fog::amount`
+fn fog_amount(dist: f32) -> f32 {
+	if (fog.density > 0.0) {
+		let d = dist * fog.density;
+		return 1.0 - exp(-d * d);
+	}
+	return clamp((dist - fog.start) / (fog.end - fog.start), 0.0, 1.0);
+}
+
+// block light is a little warmer than sunlight, like a torch
+let BLOCK_LIGHT_COLOR: vec3<f32> = vec3<f32>(1.0, 0.9, 0.75);
+
+// the lit and fogged color of a fragment, with the alpha of the texture
+fn shade(in: VertexOutput) -> vec4<f32> {
+	let color = textureSample(texture, texture_s, in.tex_coords);
+
+	// lambert diffuse, plus ambient so faces in shadow aren't black
+	let diffuse = max(dot(in.normal, lighting.sun_dir), 0.0);
+	let sun = lighting.ambient + (1.0 - lighting.ambient) * diffuse * lighting.sun_color;
+
+	// the sun only reaches places the sky light does, block light is the same in every direction
+	let light = max(sun * in.sky_light * lighting.sky_light, BLOCK_LIGHT_COLOR * in.block_light);
+	let lit = color.rgb * light * in.ao;
+	return vec4<f32>(mix(lit, fog.color, fog_amount(in.view_dist)), color.a);
+}
+
+@fragment
+fn fs_main(
+	in: VertexOutput
+) -> @location(0) vec4<f32> {
+	return shade(in);
+}
+
+// for blocks like leaves, that have holes in them instead of being see-through
+@fragment
+fn fs_cutout(
+	in: VertexOutput
+) -> @location(0) vec4<f32> {
+	let color = shade(in);
+	if (color.a < 0.5) {
+		discard;
+	}
+	return color;
+}
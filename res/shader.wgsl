@@ -3,10 +3,16 @@
 struct VertexInput {
 	@location(0) pos: vec3<f32>,
 	@location(1) tex_coords: vec2<f32>,
+	@location(2) normal: vec3<f32>,
 }
 struct VertexOutput {
 	@builtin(position) clip_position: vec4<f32>,
 	@location(0) tex_coords: vec2<f32>,
+	@location(1) normal: vec3<f32>,
+	// how far the vertex is from the camera, for fog
+	@location(2) view_dist: f32,
+	// multiplies the color of the texture
+	@location(3) color: vec4<f32>,
 }
 
 @group(1) @binding(0)
@@ -19,22 +25,95 @@ var<uniform> proj_mat: mat4x4<f32>;
 fn vs_main(in: VertexInput) -> VertexOutput {
 	var out: VertexOutput;
 	
-	out.clip_position = proj_mat * view_mat * vec4<f32>(in.pos, 1.0);
+	let view_pos = view_mat * vec4<f32>(in.pos, 1.0);
+	out.clip_position = proj_mat * view_pos;
+	out.view_dist = length(view_pos.xyz);
 	out.tex_coords = in.tex_coords;
+	out.normal = in.normal;
+	out.color = vec4<f32>(1.0);
 	
 	return out;
 }
 
+// `instancing::Instance`, with the transformation one column per location
+struct InstanceInput {
+	@location(3) transform_0: vec4<f32>,
+	@location(4) transform_1: vec4<f32>,
+	@location(5) transform_2: vec4<f32>,
+	@location(6) transform_3: vec4<f32>,
+	@location(7) color: vec4<f32>,
+}
+
+@vertex
+fn vs_instanced(in: VertexInput, instance: InstanceInput) -> VertexOutput {
+	let transform = mat4x4<f32>(
+		instance.transform_0,
+		instance.transform_1,
+		instance.transform_2,
+		instance.transform_3,
+	);
+	var out: VertexOutput;
+
+	let view_pos = view_mat * transform * vec4<f32>(in.pos, 1.0);
+	out.clip_position = proj_mat * view_pos;
+	out.view_dist = length(view_pos.xyz);
+	out.tex_coords = in.tex_coords;
+	out.normal = (transform * vec4<f32>(in.normal, 0.0)).xyz;
+	out.color = instance.color;
+
+	return out;
+}
+
 // Fragment shader
 
+struct Lighting {
+	// points towards the sun
+	sun_dir: vec3<f32>,
+	ambient: f32,
+	sun_color: vec3<f32>,
+	// how bright sky light is, lower at night
+	sky_light: f32,
+}
+
 @group(0) @binding(0)
 var texture: texture_2d<f32>;
 @group(0) @binding(1)
 var texture_s: sampler;
 
+struct Fog {
+	color: vec3<f32>,
+	// above 0 for exponential fog, otherwise it's linear between `start` and `end`
+	density: f32,
+	start: f32,
+	end: f32,
+}
+
+@group(1) @binding(2)
+var<uniform> lighting: Lighting;
+
+@group(1) @binding(3)
+var<uniform> fog: Fog;
+
+// how much of a point `dist` away is hidden by the fog, 0..1. The same as `Fog::amount`
+fn fog_amount(dist: f32) -> f32 {
+	if (fog.density > 0.0) {
+		let d = dist * fog.density;
+		return 1.0 - exp(-d * d);
+	}
+	return clamp((dist - fog.start) / (fog.end - fog.start), 0.0, 1.0);
+}
+
 @fragment
 fn fs_main(
 	in: VertexOutput
 ) -> @location(0) vec4<f32> {
-	return textureSample(texture, texture_s, in.tex_coords);
+	let color = textureSample(texture, texture_s, in.tex_coords) * in.color;
+	
+	// lambert diffuse, plus ambient so faces in shadow aren't black
+	let diffuse = max(dot(normalize(in.normal), lighting.sun_dir), 0.0);
+	let sun = lighting.ambient + (1.0 - lighting.ambient) * diffuse * lighting.sun_color;
+	let light = sun * lighting.sky_light;
+	
+	let lit = color.rgb * light;
+	return vec4<f32>(mix(lit, fog.color, fog_amount(in.view_dist)), color.a);
 }
@@ -0,0 +1,109 @@
+// Draws the sky behind everything else, with a single triangle covering the whole screen.
+// Each pixel works out the direction it's looking in, and colors itself by that.
+
+struct Sky {
+	// from clip space, to a world space direction (the camera's position is left out)
+	inv_view_proj: mat4x4<f32>,
+	zenith: vec3<f32>,
+	// how visible the stars are, 0 during the day and 1 at night
+	star_visibility: f32,
+	horizon: vec3<f32>,
+	// how far the stars have turned around the sky, in radians
+	star_rotation: f32,
+	// points towards the sun
+	sun_dir: vec3<f32>,
+	// half the width of the sun, as the tangent of the angle it covers
+	sun_size: f32,
+	moon_dir: vec3<f32>,
+	moon_size: f32,
+}
+
+@group(0) @binding(0)
+var sun_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var moon_texture: texture_2d<f32>;
+@group(0) @binding(2)
+var texture_s: sampler;
+
+@group(1) @binding(0)
+var<uniform> sky: Sky;
+
+struct VertexOutput {
+	@builtin(position) clip_position: vec4<f32>,
+	@location(0) ndc: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+	// a triangle twice the size of the screen, so the screen is the corner of it that's
+	// inside the right angle
+	let ndc = vec2<f32>(f32(index & 1u) * 4.0 - 1.0, f32(index >> 1u) * 4.0 - 1.0);
+
+	var out: VertexOutput;
+	out.clip_position = vec4<f32>(ndc, 0.0, 1.0);
+	out.ndc = ndc;
+	return out;
+}
+
+// A texture drawn on the sky, centered on `center`.
+fn sky_disc(dir: vec3<f32>, center: vec3<f32>, size: f32, texture: texture_2d<f32>) -> vec4<f32> {
+	// the sun and moon move around the Z axis, so it's never parallel to `center`
+	let right = normalize(cross(vec3<f32>(0.0, 0.0, 1.0), center));
+	let up = cross(center, right);
+
+	// projects `dir` onto the plane touching the sky at `center`
+	let d = dir / dot(dir, center);
+	let uv = vec2<f32>(dot(d, right), -dot(d, up)) / size * 0.5 + 0.5;
+	if (dot(dir, center) <= 0.0 || any(uv < vec2<f32>(0.0)) || any(uv > vec2<f32>(1.0))) {
+		return vec4<f32>(0.0);
+	}
+	// textureSample can't be used outside of uniform control flow
+	return textureSampleLevel(texture, texture_s, uv, 0.0);
+}
+
+fn hash(p: vec3<f32>) -> f32 {
+	return fract(sin(dot(p, vec3<f32>(12.9898, 78.233, 37.719))) * 43758.5453);
+}
+
+// Sparse points of light, fixed to the sky as it turns.
+fn stars(dir: vec3<f32>) -> f32 {
+	let c = cos(sky.star_rotation);
+	let s = sin(sky.star_rotation);
+	let rotated = vec3<f32>(c * dir.x + s * dir.y, c * dir.y - s * dir.x, dir.z);
+
+	// splits the sky into small cells, a few of which have a star
+	let p = rotated * 150.0;
+	let cell = floor(p);
+	let star = hash(cell);
+	if (star < 0.996) {
+		return 0.0;
+	}
+	// the star is a dot in the middle of its cell, some brighter than others
+	let dist = length(p - cell - 0.5);
+	return smoothstep(0.4, 0.0, dist) * (star - 0.996) / 0.004;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+	let world = sky.inv_view_proj * vec4<f32>(in.ndc, 0.5, 1.0);
+	let dir = normalize(world.xyz / world.w);
+
+	// the sky is brightest at the horizon, fading to the zenith color above it
+	let height = clamp(dir.y, 0.0, 1.0);
+	var color = mix(sky.horizon, sky.zenith, sqrt(height));
+	// and below the horizon, it fades to a little darker than the horizon
+	color = color * (1.0 - 0.3 * clamp(-dir.y * 3.0, 0.0, 1.0));
+
+	// a glow around the sun, that tints the sky near it
+	let sun_glow = pow(max(dot(dir, sky.sun_dir), 0.0), 32.0);
+	color = color + sky.horizon * sun_glow * 0.3;
+
+	color = color + vec3<f32>(stars(dir) * sky.star_visibility * step(0.0, dir.y));
+
+	let sun = sky_disc(dir, sky.sun_dir, sky.sun_size, sun_texture);
+	color = color + sun.rgb * sun.a;
+	let moon = sky_disc(dir, sky.moon_dir, sky.moon_size, moon_texture);
+	color = mix(color, moon.rgb, moon.a);
+
+	return vec4<f32>(color, 1.0);
+}
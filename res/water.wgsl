@@ -0,0 +1,85 @@
+// The surface of water. Appended to `chunk.wgsl`, so it shares its vertex format and lighting.
+
+// the time in seconds in x, for animation. The rest is unused
+@group(1) @binding(4)
+var<uniform> time: vec4<f32>;
+
+struct WaterOutput {
+	@builtin(position) clip_position: vec4<f32>,
+	// 0..1 across the face, before the texture is scrolled
+	@location(0) face_coords: vec2<f32>,
+	@location(1) @interpolate(flat) tile: vec2<f32>,
+	@location(2) normal: vec3<f32>,
+	@location(3) ao: f32,
+	@location(4) sky_light: f32,
+	@location(5) block_light: f32,
+	@location(6) view_pos: vec3<f32>,
+}
+
+// Everything here repeats a whole number of times in `ANIMATION_PERIOD` (3600 seconds),
+// so nothing jumps when the time wraps back to 0
+
+// how far the surface moves up and down
+let WAVE_HEIGHT: f32 = 0.04;
+// how many times per second each of the two waves rises and falls
+let WAVE_FREQUENCY: vec2<f32> = vec2<f32>(0.27, 0.21);
+// how many tiles per second the texture moves
+let FLOW_SPEED: vec2<f32> = vec2<f32>(0.06, 0.035);
+let TAU: f32 = 6.283185307;
+// how much light the surface reflects when looking straight at it
+let BASE_REFLECTANCE: f32 = 0.02;
+
+// The height of the waves at `pos`. Two crossing waves, so it doesn't look like stripes.
+fn wave(pos: vec3<f32>) -> f32 {
+	// the phase of each wave, wrapped so it stays precise
+	let phase = fract(time.x * WAVE_FREQUENCY) * TAU;
+	return WAVE_HEIGHT * 0.5 * (sin(pos.x * 1.3 + phase.x) + sin(pos.z * 1.1 - phase.y));
+}
+
+@vertex
+fn vs_water(in: VertexInput) -> WaterOutput {
+	var v = unpack_vertex(in.data);
+	// the surface is always lowered, so the waves don't reach above the block.
+	// Vertices at the bottom stay put, so there are no gaps against the blocks below
+	if (v.lowered > 0u) {
+		v.pos.y = v.pos.y + wave(v.pos);
+	}
+	let base = vertex_output(v);
+
+	var out: WaterOutput;
+	out.clip_position = base.clip_position;
+	out.face_coords = corner_tex_coords(v.corner);
+	out.tile = tile_pos(v.texture);
+	out.normal = base.normal;
+	out.ao = base.ao;
+	out.sky_light = base.sky_light;
+	out.block_light = base.block_light;
+	out.view_pos = (view_mat * vec4<f32>(v.pos, 1.0)).xyz;
+	return out;
+}
+
+@fragment
+fn fs_water(in: WaterOutput) -> @location(0) vec4<f32> {
+	// the texture wraps around inside its tile, so it looks like it's flowing
+	let scrolled = fract(in.face_coords + FLOW_SPEED * time.x);
+
+	var base: VertexOutput;
+	base.clip_position = in.clip_position;
+	base.tex_coords = (in.tile + scrolled) / f32(ATLAS_SIZE);
+	base.normal = in.normal;
+	base.ao = in.ao;
+	base.sky_light = in.sky_light;
+	base.block_light = in.block_light;
+	base.view_dist = length(in.view_pos);
+	let color = shade(base);
+
+	// Schlick's approximation of fresnel. The surface reflects more of the sky, and less of what's
+	// under it, the flatter the angle it's looked at
+	let view_normal = normalize((view_mat * vec4<f32>(in.normal, 0.0)).xyz);
+	let facing = abs(dot(normalize(-in.view_pos), view_normal));
+	let fresnel = BASE_REFLECTANCE + (1.0 - BASE_REFLECTANCE) * pow(1.0 - facing, 5.0);
+
+	// the fog color is the color of the sky at the horizon, which is what's mostly reflected
+	let rgb = mix(color.rgb, fog.color, fresnel);
+	return vec4<f32>(rgb, mix(color.a, 1.0, fresnel));
+}
@@ -0,0 +1,216 @@
+use crate::model::Face;
+
+/// The texture of each face of a block, as indices into `res/blocks.png`.
+/// The atlas is a grid of `ATLAS_SIZE` by `ATLAS_SIZE` tiles, indexed row by row.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlockTextures([u16; 6]);
+impl BlockTextures {
+    pub const fn all(tex: u16) -> Self {
+        Self([tex; 6])
+    }
+
+    pub const fn top_side_bottom(top: u16, side: u16, bottom: u16) -> Self {
+        Self([side, side, top, bottom, side, side])
+    }
+
+    #[inline(always)]
+    pub const fn get(&self, face: Face) -> u16 {
+        self.0[face as usize]
+    }
+}
+
+/// The number of tiles along each side of the block texture atlas.
+pub const ATLAS_SIZE: u32 = 16;
+
+/// How a block's faces are drawn, each with its own mesh and pipeline.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RenderLayer {
+    // fully opaque, ignoring the alpha of the texture
+    Opaque,
+    // pixels are either fully opaque or fully transparent, like leaves and glass
+    Cutout,
+    // blended with whatever is behind, like ice. Drawn last, from back to front
+    Translucent,
+    // blended like `Translucent`, with a shader that animates the surface of the water
+    Water,
+}
+impl RenderLayer {
+    /// In the order the layers are drawn.
+    pub const ALL: [Self; 4] = [Self::Opaque, Self::Cutout, Self::Translucent, Self::Water];
+
+    /// Whether the layer is blended with what's behind it, so it has to be drawn back to front.
+    #[inline(always)]
+    pub fn is_blended(self) -> bool {
+        matches!(self, Self::Translucent | Self::Water)
+    }
+}
+
+/// Properties shared by every block of a type.
+#[derive(Clone, Copy, Debug)]
+pub struct BlockInfo {
+    pub name: &'static str,
+    // entities collide with solid blocks
+    pub solid: bool,
+    // opaque blocks hide the faces of the blocks next to them, and block light
+    pub opaque: bool,
+    // the block light level the block gives off, 0..=`light::MAX_LIGHT`
+    pub emission: u8,
+    // liquids can be moved through and swum in, and fog the view of a camera inside them
+    pub liquid: bool,
+    // falls when there's nothing under it, see `falling_block`
+    pub falls: bool,
+    pub layer: RenderLayer,
+    pub textures: BlockTextures,
+}
+impl BlockInfo {
+    /// A solid, opaque block. Other blocks are described relative to this with struct update syntax.
+    pub const DEFAULT: Self = Self {
+        name: "",
+        solid: true,
+        opaque: true,
+        emission: 0,
+        liquid: false,
+        falls: false,
+        layer: RenderLayer::Opaque,
+        textures: BlockTextures::all(0),
+    };
+}
+
+/// A type of block, an index into `BLOCKS`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Block(pub u8);
+impl Block {
+    pub const AIR: Self = Self(0);
+    pub const STONE: Self = Self(1);
+    pub const DIRT: Self = Self(2);
+    pub const GRASS: Self = Self(3);
+    pub const SAND: Self = Self(4);
+    pub const GRAVEL: Self = Self(5);
+    pub const LOG: Self = Self(6);
+    pub const PLANKS: Self = Self(7);
+    pub const COBBLESTONE: Self = Self(8);
+    pub const GLASS: Self = Self(9);
+    pub const LAMP: Self = Self(10);
+    pub const WATER: Self = Self(11);
+    pub const LAVA: Self = Self(12);
+    pub const LEAVES: Self = Self(13);
+    pub const ICE: Self = Self(14);
+    pub const OBSIDIAN: Self = Self(15);
+
+    #[inline(always)]
+    pub fn info(self) -> &'static BlockInfo {
+        &BLOCKS[self.0 as usize]
+    }
+
+    #[inline(always)]
+    pub fn is_air(self) -> bool {
+        self == Self::AIR
+    }
+
+    /// The block type called `name`, like `"stone"`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let index = BLOCKS.iter().position(|info| info.name == name)?;
+        Some(Self(index as u8))
+    }
+}
+
+pub static BLOCKS: &[BlockInfo] = &[
+    BlockInfo {
+        name: "air",
+        solid: false,
+        opaque: false,
+        ..BlockInfo::DEFAULT
+    },
+    BlockInfo {
+        name: "stone",
+        textures: BlockTextures::all(0),
+        ..BlockInfo::DEFAULT
+    },
+    BlockInfo {
+        name: "dirt",
+        textures: BlockTextures::all(1),
+        ..BlockInfo::DEFAULT
+    },
+    BlockInfo {
+        name: "grass",
+        textures: BlockTextures::top_side_bottom(2, 3, 1),
+        ..BlockInfo::DEFAULT
+    },
+    BlockInfo {
+        name: "sand",
+        falls: true,
+        textures: BlockTextures::all(4),
+        ..BlockInfo::DEFAULT
+    },
+    BlockInfo {
+        name: "gravel",
+        falls: true,
+        textures: BlockTextures::all(5),
+        ..BlockInfo::DEFAULT
+    },
+    BlockInfo {
+        name: "log",
+        textures: BlockTextures::top_side_bottom(7, 6, 7),
+        ..BlockInfo::DEFAULT
+    },
+    BlockInfo {
+        name: "planks",
+        textures: BlockTextures::all(8),
+        ..BlockInfo::DEFAULT
+    },
+    BlockInfo {
+        name: "cobblestone",
+        textures: BlockTextures::all(16),
+        ..BlockInfo::DEFAULT
+    },
+    BlockInfo {
+        name: "glass",
+        opaque: false,
+        layer: RenderLayer::Cutout,
+        textures: BlockTextures::all(10),
+        ..BlockInfo::DEFAULT
+    },
+    BlockInfo {
+        name: "lamp",
+        emission: 15,
+        textures: BlockTextures::all(13),
+        ..BlockInfo::DEFAULT
+    },
+    BlockInfo {
+        name: "water",
+        solid: false,
+        opaque: false,
+        liquid: true,
+        layer: RenderLayer::Water,
+        textures: BlockTextures::all(11),
+        ..BlockInfo::DEFAULT
+    },
+    BlockInfo {
+        name: "lava",
+        solid: false,
+        opaque: false,
+        emission: 15,
+        liquid: true,
+        textures: BlockTextures::all(12),
+        ..BlockInfo::DEFAULT
+    },
+    BlockInfo {
+        name: "leaves",
+        opaque: false,
+        layer: RenderLayer::Cutout,
+        textures: BlockTextures::all(9),
+        ..BlockInfo::DEFAULT
+    },
+    BlockInfo {
+        name: "ice",
+        opaque: false,
+        layer: RenderLayer::Translucent,
+        textures: BlockTextures::all(15),
+        ..BlockInfo::DEFAULT
+    },
+    BlockInfo {
+        name: "obsidian",
+        textures: BlockTextures::all(14),
+        ..BlockInfo::DEFAULT
+    },
+];
@@ -0,0 +1,154 @@
+//! Blocks that change on their own, like flowing fluids and spreading grass.
+//!
+//! A block can schedule an update of itself some number of ticks ahead, usually because a block
+//! next to it changed. On top of that, a few random blocks in every chunk get a random tick each
+//! tick, for slow changes that don't need to happen at any particular time.
+//!
+//! Both only depend on the world's seed and what happened before, so replays and tests see the
+//! same blocks change on the same ticks.
+
+use crate::chunk::CHUNK_SIZE;
+use crate::math::Vec3;
+use std::collections::{BTreeMap, HashMap};
+
+/// The most scheduled updates run in a single tick. The rest wait for the following ticks,
+/// so a huge flood slows down instead of stalling the game.
+pub const MAX_UPDATES_PER_TICK: usize = 4096;
+
+/// How many blocks in each chunk get a random tick every tick.
+/// Any one block gets one about every `CHUNK_VOLUME / RANDOM_TICKS_PER_CHUNK` ticks.
+pub const RANDOM_TICKS_PER_CHUNK: usize = 3;
+
+/// The updates blocks have scheduled for later ticks.
+#[derive(Clone, Debug, Default)]
+pub struct BlockUpdates {
+    // keyed by the tick the update is due, then the order it was scheduled in
+    queue: BTreeMap<(u64, u64), Vec3<i32>>,
+    // the key in `queue` of each scheduled position, so a block isn't queued more than once
+    keys: HashMap<Vec3<i32>, (u64, u64)>,
+    next_order: u64,
+}
+impl BlockUpdates {
+    /// Schedules an update of the block at `pos` on `tick`. If one is already scheduled,
+    /// the earlier of the two is kept.
+    pub fn schedule(&mut self, pos: Vec3<i32>, tick: u64) {
+        if let Some(&key) = self.keys.get(&pos) {
+            if key.0 <= tick {
+                return;
+            }
+            self.queue.remove(&key);
+        }
+        let key = (tick, self.next_order);
+        self.next_order += 1;
+        self.queue.insert(key, pos);
+        self.keys.insert(pos, key);
+    }
+
+    /// The tick the update of the block at `pos` is due, if one is scheduled.
+    #[inline(always)]
+    pub fn scheduled_tick(&self, pos: Vec3<i32>) -> Option<u64> {
+        self.keys.get(&pos).map(|key| key.0)
+    }
+
+    /// Removes and returns up to `max` of the updates due on or before `tick`, oldest first.
+    pub fn take_due(&mut self, tick: u64, max: usize) -> Vec<Vec3<i32>> {
+        let mut updates = Vec::new();
+        while updates.len() < max {
+            let Some(entry) = self.queue.first_entry() else {
+                break;
+            };
+            if entry.key().0 > tick {
+                break;
+            }
+            let pos = entry.remove();
+            self.keys.remove(&pos);
+            updates.push(pos);
+        }
+        updates
+    }
+
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+/// A random number from `seed`, the tick, and a position. splitmix64, like `worldgen` uses.
+pub fn random(seed: u64, tick: u64, pos: Vec3<i32>) -> u64 {
+    let mut h = seed
+        ^ tick.wrapping_mul(0xD6E8FEB86659FD93)
+        ^ (pos.x as u32 as u64).wrapping_mul(0x9E3779B97F4A7C15)
+        ^ (pos.y as u32 as u64).wrapping_mul(0xA0761D6478BD642F)
+        ^ (pos.z as u32 as u64).wrapping_mul(0xC2B2AE3D27D4EB4F);
+    h = (h ^ (h >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    h = (h ^ (h >> 27)).wrapping_mul(0x94D049BB133111EB);
+    h ^ (h >> 31)
+}
+
+/// The local positions of the blocks in the chunk at `chunk_pos` that get a random tick on
+/// `tick`. Can pick the same block more than once.
+pub fn random_tick_positions(
+    seed: u64,
+    tick: u64,
+    chunk_pos: Vec3<i32>,
+) -> [Vec3<i32>; RANDOM_TICKS_PER_CHUNK] {
+    let bits = random(seed, tick, chunk_pos);
+    // 4 bits per axis, 12 bits per block
+    std::array::from_fn(|i| {
+        let bits = bits >> (i * 12);
+        let axis = |shift: usize| (bits >> shift) as i32 & (CHUNK_SIZE - 1);
+        Vec3::new(axis(0), axis(4), axis(8))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn updates_run_once_when_due() {
+        let mut updates = BlockUpdates::default();
+        let [a, b, c] = [0, 1, 2].map(|x| Vec3::new(x, 0, 0));
+        updates.schedule(b, 5);
+        updates.schedule(a, 5);
+        updates.schedule(c, 3);
+        // scheduling again keeps the earliest tick
+        updates.schedule(b, 8);
+        updates.schedule(a, 4);
+        assert_eq!(updates.len(), 3);
+        assert_eq!(updates.scheduled_tick(b), Some(5));
+
+        assert!(updates.take_due(2, 10).is_empty());
+        assert_eq!(updates.take_due(4, 10), [c, a]);
+        assert_eq!(updates.take_due(10, 10), [b]);
+        assert!(updates.is_empty());
+
+        // once taken, a block can be scheduled again
+        updates.schedule(b, 12);
+        assert_eq!(updates.take_due(12, 10), [b]);
+    }
+
+    #[test]
+    fn random_ticks_only_depend_on_the_seed_tick_and_chunk() {
+        let chunk = Vec3::new(2, -1, 3);
+        let picks = random_tick_positions(7, 100, chunk);
+        assert_eq!(picks, random_tick_positions(7, 100, chunk));
+        assert_ne!(picks, random_tick_positions(8, 100, chunk));
+        assert_ne!(picks, random_tick_positions(7, 101, chunk));
+
+        // and spread out over the whole chunk
+        let mut counts = [0; CHUNK_SIZE as usize];
+        for tick in 0..1000 {
+            for pos in random_tick_positions(7, tick, chunk) {
+                assert!(pos.x >= 0 && pos.y >= 0 && pos.z >= 0, "{pos:?}");
+                counts[pos.y as usize] += 1;
+            }
+        }
+        assert!(counts.iter().all(|&count| count > 100), "{counts:?}");
+    }
+}
@@ -1,23 +1,24 @@
-use crate::math::{Mat4, Vec3};
+use crate::math::{Mat4, Quat, Vec3};
+
+/// The direction a camera with no rotation is facing.
+pub const FORWARD: Vec3<f32> = Vec3::new(0.0, 0.0, -1.0);
+
+/// Takes a rotation (the rotation around the X, Y, and Z axis), and
+/// creates the orientation of a camera with that rotation.<p>
+/// the rotation values should be in radians (0..TAU)
+pub fn axis_rot_to_quat(rot: Vec3<f32>) -> Quat {
+    // a positive X rotation tilts the camera down, which is a negative
+    // (clockwise) rotation about the X axis
+    Quat::from_euler(Vec3::new(-rot.x, rot.y, rot.z))
+}
 
 /// Takes a rotation (the rotation around the X, Y, and Z axis), and
 /// creates a normalized vector ray in the facing direction.<p>
 /// the rotation values should be in radians (0..TAU)
 pub fn axis_rot_to_ray(rot: Vec3<f32>) -> Vec3<f32> {
-    // the Z rotation doesn't effect the ray
-    // the Y rotation effects the ray's X and Z
-    // the X rotation effects the ray's X, Y, and Z
-
-    // the ray's X, Z is along the edge of a circle, cutting through the y-axis, radius R
-    // R goes follows 0..1..0, and is derived from the X rotation (aka the vertical tilt)
-    // the ray's Y is also derived from the X rotation
-
-    // radius of Y axis cross-sections
-    let r = rot.x.cos();
-    let x = r * -rot.y.sin();
-    let z = r * -rot.y.cos();
-    let y = -rot.x.sin();
-    Vec3 { x, y, z }
+    // derived from the same orientation as the view matrix, so the
+    // look ray and what's drawn in the center of the screen never disagree
+    axis_rot_to_quat(rot).rotate(FORWARD)
 }
 
 #[derive(Clone)]
@@ -31,9 +32,22 @@ impl Cam {
         Self { pos, rot }
     }
 
+    #[inline(always)]
+    pub fn orientation(&self) -> Quat {
+        axis_rot_to_quat(self.rot.map(f32::to_radians))
+    }
+
     #[inline(always)]
     pub fn matrix(&self) -> Mat4 {
-        Mat4::view(self.pos, self.rot.map(f32::to_radians))
+        Mat4::view_quat(self.pos, self.orientation())
+    }
+
+    /// Creates the view matrix of a camera part way between `prev` and `self`.
+    /// `t` is in the range 0..1, where 0 is `prev` and 1 is `self`.
+    pub fn interpolated_matrix(&self, prev: &Cam, t: f32) -> Mat4 {
+        let pos = prev.pos + (self.pos - prev.pos) * t;
+        let orientation = Quat::slerp(prev.orientation(), self.orientation(), t);
+        Mat4::view_quat(pos, orientation)
     }
 
     #[inline(always)]
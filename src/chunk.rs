@@ -0,0 +1,113 @@
+use crate::block::Block;
+use crate::light::LightKind;
+use crate::math::Vec3;
+
+/// The number of blocks along each side of a chunk.
+pub const CHUNK_SIZE: i32 = 16;
+pub const CHUNK_VOLUME: usize = (CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE) as usize;
+
+/// The position of the chunk containing the block at `pos`.
+#[inline(always)]
+pub fn chunk_pos(pos: Vec3<i32>) -> Vec3<i32> {
+    pos.map(|e| e.div_euclid(CHUNK_SIZE))
+}
+
+/// The position of the block at `pos`, relative to the chunk containing it.
+#[inline(always)]
+pub fn local_pos(pos: Vec3<i32>) -> Vec3<i32> {
+    pos.map(|e| e.rem_euclid(CHUNK_SIZE))
+}
+
+/// The world position of the block at the origin of the chunk at `chunk_pos`.
+#[inline(always)]
+pub fn chunk_origin(chunk_pos: Vec3<i32>) -> Vec3<i32> {
+    chunk_pos * CHUNK_SIZE
+}
+
+/// A cube of `CHUNK_SIZE`^3 blocks, and the light in each of them.
+#[derive(Clone)]
+pub struct Chunk {
+    blocks: Box<[Block; CHUNK_VOLUME]>,
+    // sky light in the high 4 bits, block light in the low 4 bits
+    light: Box<[u8; CHUNK_VOLUME]>,
+    // the level of each fluid block, see `fluid`. Unused by other blocks
+    levels: Box<[u8; CHUNK_VOLUME]>,
+}
+impl Chunk {
+    pub fn new() -> Self {
+        Self::filled(Block::AIR)
+    }
+
+    pub fn filled(block: Block) -> Self {
+        Self {
+            blocks: Box::new([block; CHUNK_VOLUME]),
+            light: Box::new([0; CHUNK_VOLUME]),
+            levels: Box::new([0; CHUNK_VOLUME]),
+        }
+    }
+
+    /// The index of the block at `local` in the block array.
+    /// Expects every element of `local` to be in `0..CHUNK_SIZE`.
+    #[inline(always)]
+    pub fn index(local: Vec3<i32>) -> usize {
+        debug_assert!(local.x >= 0 && local.x < CHUNK_SIZE, "{local:?}");
+        debug_assert!(local.y >= 0 && local.y < CHUNK_SIZE, "{local:?}");
+        debug_assert!(local.z >= 0 && local.z < CHUNK_SIZE, "{local:?}");
+        (local.x + local.z * CHUNK_SIZE + local.y * CHUNK_SIZE * CHUNK_SIZE) as usize
+    }
+
+    #[inline(always)]
+    pub fn get(&self, local: Vec3<i32>) -> Block {
+        self.blocks[Self::index(local)]
+    }
+
+    #[inline(always)]
+    pub fn set(&mut self, local: Vec3<i32>, block: Block) {
+        self.blocks[Self::index(local)] = block;
+    }
+
+    /// The level of the fluid at `local`, see `fluid`.
+    #[inline(always)]
+    pub fn level(&self, local: Vec3<i32>) -> u8 {
+        self.levels[Self::index(local)]
+    }
+
+    #[inline(always)]
+    pub fn set_level(&mut self, local: Vec3<i32>, level: u8) {
+        self.levels[Self::index(local)] = level;
+    }
+
+    /// The light level (0..=`light::MAX_LIGHT`) of `kind` at `local`.
+    #[inline(always)]
+    pub fn light(&self, local: Vec3<i32>, kind: LightKind) -> u8 {
+        let light = self.light[Self::index(local)];
+        match kind {
+            LightKind::Sky => light >> 4,
+            LightKind::Block => light & 0xF,
+        }
+    }
+
+    #[inline(always)]
+    pub fn set_light(&mut self, local: Vec3<i32>, kind: LightKind, level: u8) {
+        let light = &mut self.light[Self::index(local)];
+        *light = match kind {
+            LightKind::Sky => (*light & 0x0F) | level << 4,
+            LightKind::Block => (*light & 0xF0) | level,
+        };
+    }
+
+    /// The sky and block light at `local`, packed like they are stored.
+    #[inline(always)]
+    pub fn packed_light(&self, local: Vec3<i32>) -> u8 {
+        self.light[Self::index(local)]
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.iter().all(|block| block.is_air())
+    }
+}
+impl Default for Chunk {
+    fn default() -> Self {
+        Self::new()
+    }
+}
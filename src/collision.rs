@@ -0,0 +1,208 @@
+//! Moving boxes through the world without passing through solid blocks.
+//! Shared by the player and every entity.
+
+use crate::math::Vec3;
+use crate::world::World;
+
+/// How far from an integer a box's edge can be and still count as touching the block there.
+/// Keeps rounding errors from letting a box slip into a block it's resting against.
+const EPSILON: f32 = 1e-4;
+
+/// An axis aligned bounding box.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3<f32>,
+    pub max: Vec3<f32>,
+}
+impl Aabb {
+    #[inline(always)]
+    pub const fn new(min: Vec3<f32>, max: Vec3<f32>) -> Self {
+        Self { min, max }
+    }
+
+    /// A box `size` big, with the center of its bottom face at `pos`.
+    pub fn from_bottom_center(pos: Vec3<f32>, size: Vec3<f32>) -> Self {
+        let half = Vec3::new(size.x / 2.0, 0.0, size.z / 2.0);
+        Self::new(pos - half, pos + half + Vec3::new(0.0, size.y, 0.0))
+    }
+
+    #[inline(always)]
+    pub fn translate(self, offset: Vec3<f32>) -> Self {
+        Self::new(self.min + offset, self.max + offset)
+    }
+
+    /// Whether the box overlaps any solid block.
+    pub fn intersects_blocks(self, world: &World) -> bool {
+        let min = self.min.map(|e| (e + EPSILON).floor() as i32);
+        let max = self.max.map(|e| (e - EPSILON).ceil() as i32 - 1);
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                for z in min.z..=max.z {
+                    if world.get_block(Vec3::new(x, y, z)).info().solid {
+                        return true;
+                    }
+                }
+            }
+        }
+        false
+    }
+}
+
+/// Which axes a move was stopped on by a block.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Collisions {
+    pub x: bool,
+    pub y: bool,
+    pub z: bool,
+}
+
+/// Moves `aabb` by `delta`, one axis at a time (Y first), stopping each at the first solid
+/// block in the way. Blocks the box already overlaps don't stop it, so it can't get stuck.
+/// Returns how far the box actually moved, and which axes were blocked.
+pub fn move_aabb(world: &World, mut aabb: Aabb, delta: Vec3<f32>) -> (Vec3<f32>, Collisions) {
+    let mut moved = Vec3::all(0.0);
+    let mut collisions = Collisions::default();
+    for axis in [1, 0, 2] {
+        let d = get(delta, axis);
+        let allowed = sweep(world, aabb, axis, d);
+        let mut offset = Vec3::all(0.0);
+        set(&mut offset, axis, allowed);
+        aabb = aabb.translate(offset);
+        moved = moved + offset;
+        let blocked = allowed != d;
+        match axis {
+            0 => collisions.x = blocked,
+            1 => collisions.y = blocked,
+            _ => collisions.z = blocked,
+        }
+    }
+    (moved, collisions)
+}
+
+/// How far `aabb` can move along `axis` (0 for X, 1 for Y, 2 for Z), up to `d`.
+fn sweep(world: &World, aabb: Aabb, axis: usize, d: f32) -> f32 {
+    if d == 0.0 {
+        return 0.0;
+    }
+    // the blocks the box covers on the other two axes
+    let min = aabb.min.map(|e| (e + EPSILON).floor() as i32);
+    let max = aabb.max.map(|e| (e - EPSILON).ceil() as i32 - 1);
+    let (a, b) = match axis {
+        0 => (1, 2),
+        1 => (0, 2),
+        _ => (0, 1),
+    };
+    let solid_layer = |layer: i32| {
+        for i in get(min, a)..=get(max, a) {
+            for j in get(min, b)..=get(max, b) {
+                let mut pos = Vec3::all(0);
+                set(&mut pos, axis, layer);
+                set(&mut pos, a, i);
+                set(&mut pos, b, j);
+                if world.get_block(pos).info().solid {
+                    return true;
+                }
+            }
+        }
+        false
+    };
+
+    if d > 0.0 {
+        let edge = get(aabb.max, axis);
+        let first = (edge - EPSILON).ceil() as i32;
+        let last = (edge + d).ceil() as i32 - 1;
+        for layer in first..=last {
+            if solid_layer(layer) {
+                return (layer as f32 - edge).clamp(0.0, d);
+            }
+        }
+    } else {
+        let edge = get(aabb.min, axis);
+        let first = (edge + EPSILON).floor() as i32 - 1;
+        let last = (edge + d).floor() as i32;
+        for layer in (last..=first).rev() {
+            if solid_layer(layer) {
+                return (layer as f32 + 1.0 - edge).clamp(d, 0.0);
+            }
+        }
+    }
+    d
+}
+
+#[inline(always)]
+fn get<T: Copy>(v: Vec3<T>, axis: usize) -> T {
+    match axis {
+        0 => v.x,
+        1 => v.y,
+        _ => v.z,
+    }
+}
+
+#[inline(always)]
+fn set<T: Copy>(v: &mut Vec3<T>, axis: usize, value: T) {
+    match axis {
+        0 => v.x = value,
+        1 => v.y = value,
+        _ => v.z = value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::Block;
+
+    /// A world with a stone floor at y = 0 and a wall at x = 3, from -4 to 4 on X and Z.
+    fn room() -> World {
+        let mut world = World::with_floor(4);
+        for z in -4..=4 {
+            world.set_block(Vec3::new(3, 1, z), Block::STONE);
+        }
+        world
+    }
+
+    #[test]
+    fn boxes_stop_at_solid_blocks() {
+        let world = room();
+        let aabb = Aabb::from_bottom_center(Vec3::new(0.5, 3.0, 0.5), Vec3::new(0.6, 1.8, 0.6));
+
+        let (moved, collisions) = move_aabb(&world, aabb, Vec3::new(0.0, -5.0, 0.0));
+        assert!((moved.y + 2.0).abs() < 1e-5, "{moved:?}");
+        assert!(collisions.y && !collisions.x && !collisions.z);
+
+        // sliding along the floor into the wall
+        let aabb = aabb.translate(moved);
+        let (moved, collisions) = move_aabb(&world, aabb, Vec3::new(4.0, -1.0, 1.0));
+        assert!((moved.x - 2.2).abs() < 1e-5, "{moved:?}");
+        assert_eq!(moved.y, 0.0);
+        assert_eq!(moved.z, 1.0);
+        assert_eq!(
+            collisions,
+            Collisions {
+                x: true,
+                y: true,
+                z: false
+            }
+        );
+        assert!(!aabb.translate(moved).intersects_blocks(&world));
+    }
+
+    #[test]
+    fn boxes_fall_through_non_solid_blocks() {
+        let mut world = room();
+        world.set_block(Vec3::new(0, 1, 0), Block::WATER);
+        let aabb = Aabb::from_bottom_center(Vec3::new(0.5, 3.0, 0.5), Vec3::all(0.98));
+        let (moved, _) = move_aabb(&world, aabb, Vec3::new(0.0, -5.0, 0.0));
+        assert!((moved.y + 2.0).abs() < 1e-5, "{moved:?}");
+    }
+
+    #[test]
+    fn boxes_inside_blocks_can_move_out() {
+        let world = room();
+        let aabb = Aabb::from_bottom_center(Vec3::new(0.5, 0.25, 0.5), Vec3::all(0.5));
+        assert!(aabb.intersects_blocks(&world));
+        let (moved, collisions) = move_aabb(&world, aabb, Vec3::new(0.0, 2.0, 0.0));
+        assert_eq!(moved.y, 2.0);
+        assert!(!collisions.y);
+    }
+}
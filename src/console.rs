@@ -0,0 +1,224 @@
+//! A command line for changing the world while playing, opened by typing `/`.
+//!
+//! It's driven by the input of each tick, like the rest of the world,
+//! so commands are recorded and replayed along with everything else.
+
+use crate::block::Block;
+use crate::input::{FrameInput, Key};
+use crate::item;
+use crate::math::Vec3;
+use crate::projectile;
+use crate::sky;
+use crate::world::World;
+
+#[derive(Clone, Debug, Default)]
+pub struct Console {
+    // the command being typed, without the leading `/`. `None` while the console is closed
+    line: Option<String>,
+}
+impl Console {
+    #[inline(always)]
+    pub fn is_open(&self) -> bool {
+        self.line.is_some()
+    }
+
+    /// The command being typed, if the console is open.
+    #[inline(always)]
+    pub fn line(&self) -> Option<&str> {
+        self.line.as_deref()
+    }
+
+    /// Types `input` into the console.
+    /// Returns the command line that was entered, if enter was pressed.
+    pub fn update(&mut self, input: &FrameInput) -> Option<String> {
+        let Some(line) = &mut self.line else {
+            // anything typed after the slash in the same tick is the start of the command
+            if let Some(start) = input.text_input().find('/') {
+                self.line = Some(input.text_input()[start + 1..].to_owned());
+            }
+            return None;
+        };
+        if input.key_just_pressed(Key::Escape) {
+            self.line = None;
+            return None;
+        }
+        if input.key_typed(Key::Back) {
+            line.pop();
+        }
+        line.push_str(input.text_input());
+        if input.key_just_pressed(Key::Return) {
+            return self.line.take();
+        }
+        None
+    }
+}
+
+/// Runs a command, like `time set noon`.
+/// Returns a message describing what it did, or why it couldn't be run.
+pub fn run_command(world: &mut World, line: &str) -> Result<String, String> {
+    let args: Vec<&str> = line.split_whitespace().collect();
+    match args.as_slice() {
+        ["time"] | ["time", "query"] => Ok(format!(
+            "the time is {}{}",
+            world.time,
+            if world.time_frozen { " (frozen)" } else { "" }
+        )),
+        ["time", "set", time] => {
+            world.time = parse_time(time)?;
+            Ok(format!("set the time to {}", world.time))
+        }
+        ["time", "add", ticks] => {
+            let ticks = parse_ticks(ticks)?;
+            world.time = (world.time + ticks) % sky::DAY_LENGTH;
+            Ok(format!("set the time to {}", world.time))
+        }
+        ["time", "freeze"] => {
+            world.time_frozen = true;
+            Ok(String::from("froze the time"))
+        }
+        ["time", "unfreeze"] => {
+            world.time_frozen = false;
+            Ok(String::from("unfroze the time"))
+        }
+        ["time", ..] => Err(String::from(
+            "usage: time [query | set <ticks|sunrise|day|noon|sunset|night|midnight> \
+             | add <ticks> | freeze | unfreeze]",
+        )),
+        ["setblock", x, y, z, block] => {
+            let pos = Vec3::new(parse_coord(x)?, parse_coord(y)?, parse_coord(z)?);
+            let block = Block::from_name(block).ok_or_else(|| format!("unknown block: {block}"))?;
+            world.set_block(pos, block);
+            Ok(format!(
+                "set the block at {} {} {} to {}",
+                pos.x,
+                pos.y,
+                pos.z,
+                block.info().name
+            ))
+        }
+        ["setblock", ..] => Err(String::from("usage: setblock <x> <y> <z> <block>")),
+        ["summon", kind @ ("item" | "projectile"), block] => {
+            let block = Block::from_name(block).ok_or_else(|| format!("unknown block: {block}"))?;
+            // in front of the player's eyes, which both kinds are small enough to fit between
+            let dir = world.player.cam.dir();
+            let pos = world.player.pos + dir * 0.5 - Vec3::new(0.0, item::SIZE / 2.0, 0.0);
+            world.entities.push(match *kind {
+                "item" => item::entity(block, pos),
+                _ => projectile::thrown(block, pos, dir),
+            });
+            Ok(format!("summoned a {} {kind}", block.info().name))
+        }
+        ["summon", ..] => Err(String::from("usage: summon <item|projectile> <block>")),
+        [] => Err(String::from("no command entered")),
+        [command, ..] => Err(format!("unknown command: {command}")),
+    }
+}
+
+fn parse_coord(coord: &str) -> Result<i32, String> {
+    coord
+        .parse::<i32>()
+        .map_err(|_| format!("expected a block coordinate, got {coord:?}"))
+}
+
+fn parse_ticks(ticks: &str) -> Result<u32, String> {
+    ticks
+        .parse::<u32>()
+        .map(|ticks| ticks % sky::DAY_LENGTH)
+        .map_err(|_| format!("expected a number of ticks, got {ticks:?}"))
+}
+
+fn parse_time(time: &str) -> Result<u32, String> {
+    match time {
+        "sunrise" => Ok(sky::SUNRISE),
+        "day" => Ok(sky::DAY_START),
+        "noon" => Ok(sky::NOON),
+        "sunset" => Ok(sky::SUNSET),
+        // a little after sunset, when it's fully dark out
+        "night" => Ok(sky::SUNSET + sky::DAY_LENGTH / 24),
+        "midnight" => Ok(sky::MIDNIGHT),
+        ticks => parse_ticks(ticks),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::EntityKind;
+
+    fn typed(text: &str) -> FrameInput {
+        let mut input = FrameInput::new();
+        text.chars().for_each(|c| input.receive_char(c));
+        input
+    }
+
+    #[test]
+    fn typing_a_command() {
+        let mut console = Console::default();
+        assert_eq!(console.update(&typed("w")), None);
+        assert!(!console.is_open());
+
+        assert_eq!(console.update(&typed("/tim")), None);
+        assert_eq!(console.line(), Some("tim"));
+
+        console.update(&typed("x"));
+        let mut input = FrameInput::new();
+        input.press_key(Key::Back);
+        console.update(&input);
+        assert_eq!(console.line(), Some("tim"));
+
+        let mut input = typed("e set noon");
+        input.press_key(Key::Return);
+        assert_eq!(console.update(&input).as_deref(), Some("time set noon"));
+        assert!(!console.is_open());
+    }
+
+    #[test]
+    fn time_commands() {
+        let mut world = World::empty(0);
+        run_command(&mut world, "time set noon").unwrap();
+        assert_eq!(world.time, sky::NOON);
+        run_command(&mut world, "time set 100").unwrap();
+        assert_eq!(world.time, 100);
+        run_command(&mut world, &format!("time add {}", sky::DAY_LENGTH - 50)).unwrap();
+        assert_eq!(world.time, 50);
+
+        run_command(&mut world, "time freeze").unwrap();
+        world.tick(&FrameInput::new());
+        assert_eq!(world.time, 50);
+        run_command(&mut world, "time unfreeze").unwrap();
+        world.tick(&FrameInput::new());
+        assert_eq!(world.time, 51);
+
+        assert!(run_command(&mut world, "time set later").is_err());
+        assert!(run_command(&mut world, "weather clear").is_err());
+    }
+
+    #[test]
+    fn setblock_command() {
+        let mut world = World::empty(0);
+        run_command(&mut world, "setblock 1 -2 3 water").unwrap();
+        assert_eq!(world.get_block(Vec3::new(1, -2, 3)), Block::WATER);
+
+        assert!(run_command(&mut world, "setblock 1 2 3 cheese").is_err());
+        assert!(run_command(&mut world, "setblock 1 2.5 3 stone").is_err());
+    }
+
+    #[test]
+    fn summon_commands() {
+        let mut world = World::empty(0);
+        run_command(&mut world, "summon projectile sand").unwrap();
+        run_command(&mut world, "summon item dirt").unwrap();
+        let kinds: Vec<_> = world.entities.iter().map(|entity| entity.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                EntityKind::Projectile(Block::SAND),
+                EntityKind::Item(Block::DIRT)
+            ]
+        );
+        assert!(world.entities[0].vel.len() > 0.0);
+
+        assert!(run_command(&mut world, "summon item nothing").is_err());
+        assert!(run_command(&mut world, "summon pig dirt").is_err());
+    }
+}
@@ -0,0 +1,216 @@
+//! Things that move around the world on their own, like falling blocks, items and projectiles.
+//!
+//! Every entity is stored in `World::entities` as an `Entity`, with what's specific to it in
+//! its `EntityKind`. They all move with the same physics and collision (see `collision`) as
+//! the player, and are drawn as their `EntityModel`, stretched to their size.
+
+use crate::block::Block;
+use crate::chunk::chunk_pos;
+use crate::collision::{self, Aabb};
+use crate::falling_block;
+use crate::instancing::Instance;
+use crate::item;
+use crate::math::{Mat4, Quat, Vec3};
+use crate::projectile;
+use crate::world::World;
+
+/// How much faster entities with gravity fall each tick, in blocks per tick.
+pub const GRAVITY: f32 = 0.04;
+/// The fastest an entity falls, in blocks per tick.
+pub const TERMINAL_VELOCITY: f32 = 2.0;
+/// How much of its horizontal velocity an entity keeps each tick, in the air and on the ground.
+pub const AIR_DRAG: f32 = 0.98;
+pub const GROUND_FRICTION: f32 = 0.6;
+
+/// What an entity is, and what it does each tick besides moving.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EntityKind {
+    // lands back in the world as the block, see `falling_block`
+    FallingBlock(Block),
+    // the block lying around, see `item`
+    Item(Block),
+    // the block thrown through the air, see `projectile`
+    Projectile(Block),
+}
+
+/// How an entity is drawn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EntityModel {
+    // `model::default_model` textured like the block
+    Block(Block),
+}
+
+#[derive(Clone, Debug)]
+pub struct Entity {
+    pub kind: EntityKind,
+    pub model: EntityModel,
+    // the center of the bottom of its bounding box
+    pub pos: Vec3<f32>,
+    // in blocks per tick
+    pub vel: Vec3<f32>,
+    // the size of its bounding box, and of its model
+    pub size: Vec3<f32>,
+    // multiplies the color of its model
+    pub color: [f32; 4],
+    // turns the model about the center of its bottom. The bounding box is always axis aligned
+    pub orientation: Quat,
+    pub gravity: bool,
+    // whether it was stopped by a block below it on the last tick
+    pub on_ground: bool,
+    // whether it was stopped by a block on any axis on the last tick
+    pub collided: bool,
+    // how many ticks it's been around
+    pub age: u32,
+
+    // where it was before the last tick, for interpolating between ticks
+    pub prev_pos: Vec3<f32>,
+    pub prev_orientation: Quat,
+}
+impl Entity {
+    pub fn new(kind: EntityKind, model: EntityModel, pos: Vec3<f32>, size: Vec3<f32>) -> Self {
+        Self {
+            kind,
+            model,
+            pos,
+            vel: Vec3::all(0.0),
+            size,
+            color: Instance::WHITE,
+            orientation: Quat::identity(),
+            gravity: true,
+            on_ground: false,
+            collided: false,
+            age: 0,
+            prev_pos: pos,
+            prev_orientation: Quat::identity(),
+        }
+    }
+
+    #[inline(always)]
+    pub fn aabb(&self) -> Aabb {
+        Aabb::from_bottom_center(self.pos, self.size)
+    }
+
+    /// Turns the entity's model by `degrees` about `axis`, which should be normalized.
+    pub fn turn(&mut self, axis: Vec3<f32>, degrees: f32) {
+        let turn = Quat::from_axis_angle(axis, degrees.to_radians());
+        self.orientation = (turn * self.orientation).norm();
+    }
+
+    /// The transformation of the entity's model, part way between where it was on the last tick
+    /// and where it is now. `t` is in the range 0..1, where 0 is the last tick.
+    /// Models are a unit in size, with the center of their bottom at the origin.
+    pub fn transformation(&self, t: f32) -> Mat4 {
+        let pos = self.prev_pos.lerp(self.pos, t);
+        let orientation = Quat::slerp(self.prev_orientation, self.orientation, t);
+        Mat4::translation(pos) * Mat4::rotation(orientation) * Mat4::scaling(self.size)
+    }
+
+    /// Applies gravity and drag, and moves the entity by its velocity until it hits a block.
+    fn step(&mut self, world: &World) {
+        self.prev_pos = self.pos;
+        self.prev_orientation = self.orientation;
+        self.age += 1;
+        if self.gravity {
+            self.vel.y = (self.vel.y - GRAVITY).max(-TERMINAL_VELOCITY);
+        }
+
+        let (moved, collisions) = collision::move_aabb(world, self.aabb(), self.vel);
+        self.pos = self.pos + moved;
+        self.on_ground = collisions.y && self.vel.y < 0.0;
+        self.collided = collisions.x || collisions.y || collisions.z;
+        if collisions.x {
+            self.vel.x = 0.0;
+        }
+        if collisions.y {
+            self.vel.y = 0.0;
+        }
+        if collisions.z {
+            self.vel.z = 0.0;
+        }
+
+        let drag = if self.on_ground {
+            GROUND_FRICTION
+        } else {
+            AIR_DRAG
+        };
+        self.vel.x *= drag;
+        self.vel.z *= drag;
+    }
+}
+
+/// Moves every entity, then lets each do what its kind does.
+/// Entities that fall out of the loaded world are removed.
+pub fn update_all(world: &mut World) {
+    let mut entities = std::mem::take(&mut world.entities);
+    entities.retain_mut(|entity| {
+        entity.step(world);
+        if world.chunk(chunk_pos(entity.pos.floor_i32())).is_none() {
+            return false;
+        }
+        match entity.kind {
+            EntityKind::FallingBlock(block) => falling_block::update_entity(world, entity, block),
+            EntityKind::Item(_) => item::update_entity(entity),
+            EntityKind::Projectile(block) => projectile::update_entity(world, entity, block),
+        }
+    });
+    // entities spawned while updating the others start moving on the next tick
+    entities.append(&mut world.entities);
+    world.entities = entities;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entities_are_stopped_by_blocks() {
+        let mut world = World::with_floor(4);
+        for y in 1..=8 {
+            for z in -4..=4 {
+                world.set_block(Vec3::new(3, y, z), Block::STONE);
+            }
+        }
+        // thrown at the wall, so it slides down it and lands against it
+        let mut entity = falling_block::entity(Block::SAND, Vec3::new(0, 5, 0));
+        entity.vel = Vec3::new(0.5, 0.2, 0.0);
+        world.entities.push(entity);
+
+        world.tick_n(1);
+        assert_eq!(world.entities[0].vel.x, 0.5 * AIR_DRAG);
+        world.tick_n(10);
+        let entity = &world.entities[0];
+        assert_eq!(entity.vel.x, 0.0);
+        assert!(
+            (entity.aabb().max.x - 3.0).abs() < 1e-4,
+            "{:?}",
+            entity.aabb()
+        );
+        assert!(entity.pos.y < 5.0, "{:?}", entity.pos);
+
+        world.tick_n(60);
+        assert!(world.entities.is_empty());
+        assert_eq!(world.get_block(Vec3::new(2, 1, 0)), Block::SAND);
+    }
+
+    #[test]
+    fn transformation_places_the_model_on_the_entity() {
+        let mut entity = Entity::new(
+            EntityKind::FallingBlock(Block::SAND),
+            EntityModel::Block(Block::SAND),
+            Vec3::new(2.5, 3.0, -1.5),
+            Vec3::all(2.0),
+        );
+        entity.prev_pos = Vec3::new(2.5, 5.0, -1.5);
+        let corner = |m: Mat4, v: Vec3<f32>| m * crate::math::Vec4::new(v.x, v.y, v.z, 1.0);
+
+        let m = entity.transformation(1.0);
+        let top = corner(m, Vec3::new(0.5, 1.0, 0.5));
+        assert!(
+            (top.x - 3.5).abs() < 1e-5 && (top.y - 5.0).abs() < 1e-5,
+            "{top:?}"
+        );
+        // halfway between the last tick and this one
+        let bottom = corner(entity.transformation(0.5), Vec3::all(0.0));
+        assert!((bottom.y - 4.0).abs() < 1e-5, "{bottom:?}");
+    }
+}
@@ -0,0 +1,117 @@
+//! Blocks that fall when there's nothing under them, like sand and gravel.
+//!
+//! An unsupported block is taken out of the world and falls as an entity, until it lands on
+//! something solid and is placed back as a block. Blocks check for support when a scheduled
+//! update reaches them (see `block_update`), which they get whenever a block next to them changes.
+
+use crate::block::Block;
+use crate::entity::{Entity, EntityKind, EntityModel};
+use crate::math::Vec3;
+use crate::world::World;
+
+/// How many ticks a block waits before falling, after the block under it is removed.
+pub const FALL_DELAY: u64 = 2;
+/// The size of a falling block. A little smaller than a block, so it fits down a one block hole.
+pub const SIZE: f32 = 0.98;
+
+/// A falling block entity, in place of the block at `pos`.
+pub fn entity(block: Block, pos: Vec3<i32>) -> Entity {
+    let pos = pos.as_f32() + Vec3::new(0.5, (1.0 - SIZE) / 2.0, 0.5);
+    let kind = EntityKind::FallingBlock(block);
+    Entity::new(kind, EntityModel::Block(block), pos, Vec3::all(SIZE))
+}
+
+/// Whether a falling block can fall through `block`.
+fn can_fall_through(block: Block) -> bool {
+    !block.info().solid
+}
+
+/// Starts the block at `pos` falling, if it's one that falls and there's nothing under it.
+pub fn update(world: &mut World, pos: Vec3<i32>) {
+    let block = world.get_block(pos);
+    if !block.info().falls || !can_fall_through(world.get_block(pos + Vec3::new(0, -1, 0))) {
+        return;
+    }
+    world.set_block(pos, Block::AIR);
+    world.entities.push(entity(block, pos));
+}
+
+/// Places the falling `block` back in the world once `entity` has landed.
+/// Returns whether it's still falling.
+pub fn update_entity(world: &mut World, entity: &Entity, block: Block) -> bool {
+    if !entity.on_ground {
+        return true;
+    }
+    // the block its center is in, which it mostly fills
+    let center = entity.pos + Vec3::new(0.0, SIZE / 2.0, 0.0);
+    let mut pos = center.floor_i32();
+    // a block falling right under this one may have landed in the same place
+    while !can_fall_through(world.get_block(pos)) {
+        pos.y += 1;
+    }
+    world.set_block(pos, block);
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn supported_blocks_stay_put() {
+        let mut world = World::with_floor(4);
+        world.set_block(Vec3::new(0, 1, 0), Block::SAND);
+        world.set_block(Vec3::new(1, 1, 0), Block::STONE);
+        world.set_block(Vec3::new(1, 2, 0), Block::GRAVEL);
+        world.tick_n(20);
+        assert!(world.entities.is_empty());
+        assert_eq!(world.get_block(Vec3::new(0, 1, 0)), Block::SAND);
+        assert_eq!(world.get_block(Vec3::new(1, 2, 0)), Block::GRAVEL);
+    }
+
+    #[test]
+    fn unsupported_blocks_fall_and_land() {
+        let mut world = World::with_floor(4);
+        world.set_block(Vec3::new(0, 8, 0), Block::SAND);
+        world.tick_n(FALL_DELAY + 1);
+        assert!(world.get_block(Vec3::new(0, 8, 0)).is_air());
+        assert_eq!(world.entities.len(), 1);
+        assert_eq!(
+            world.entities[0].kind,
+            EntityKind::FallingBlock(Block::SAND)
+        );
+
+        world.tick_n(60);
+        assert!(world.entities.is_empty());
+        assert_eq!(world.get_block(Vec3::new(0, 1, 0)), Block::SAND);
+        assert!(world.get_block(Vec3::new(0, 2, 0)).is_air());
+    }
+
+    #[test]
+    fn columns_fall_when_their_support_is_removed() {
+        let mut world = World::with_floor(4);
+        world.set_block(Vec3::new(0, 3, 0), Block::DIRT);
+        for y in 4..7 {
+            world.set_block(Vec3::new(0, y, 0), Block::GRAVEL);
+        }
+        world.tick_n(20);
+        assert!(world.entities.is_empty());
+
+        world.set_block(Vec3::new(0, 3, 0), Block::AIR);
+        world.tick_n(100);
+        assert!(world.entities.is_empty());
+        for y in 1..4 {
+            assert_eq!(world.get_block(Vec3::new(0, y, 0)), Block::GRAVEL, "{y}");
+        }
+        assert!(world.get_block(Vec3::new(0, 4, 0)).is_air());
+    }
+
+    #[test]
+    fn blocks_sink_through_fluids() {
+        let mut world = World::with_floor(4);
+        world.set_block(Vec3::new(0, 1, 0), Block::WATER);
+        world.set_block(Vec3::new(0, 5, 0), Block::SAND);
+        world.tick_n(60);
+        assert_eq!(world.get_block(Vec3::new(0, 1, 0)), Block::SAND);
+    }
+}
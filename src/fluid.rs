@@ -0,0 +1,335 @@
+//! How water and lava flow.
+//!
+//! Every fluid block has a level. Sources are level 0, and keep flowing for as long as they
+//! exist. Fluid flowing sideways out of a block is a level (or more, for lava) higher than it,
+//! until it's spread as far as it can. Fluid falling down is `FALLING`, and spreads like a
+//! source when it lands. Flowing fluid that isn't fed by anything anymore dries up.
+//!
+//! Fluids only change when a scheduled update reaches them, see `block_update`. They schedule one
+//! whenever something next to them changes, `update_delay` ticks later, so lava creeps along
+//! slower than water runs.
+
+use crate::block::Block;
+use crate::chunk::chunk_pos;
+use crate::math::Vec3;
+use crate::world::World;
+
+/// The level of a source block.
+pub const SOURCE: u8 = 0;
+/// The highest level of fluid that's flowing sideways. It doesn't spread any further.
+pub const MAX_FLOWING: u8 = 7;
+/// The level of fluid falling down from the block above.
+pub const FALLING: u8 = 8;
+
+const UP: Vec3<i32> = Vec3::new(0, 1, 0);
+const DOWN: Vec3<i32> = Vec3::new(0, -1, 0);
+const SIDES: [Vec3<i32>; 4] = [
+    Vec3::new(1, 0, 0),
+    Vec3::new(-1, 0, 0),
+    Vec3::new(0, 0, 1),
+    Vec3::new(0, 0, -1),
+];
+
+/// How many levels `fluid` goes up by every block it flows sideways.
+/// Lava is thick, and doesn't spread as far as water.
+fn level_step(fluid: Block) -> u8 {
+    match fluid {
+        Block::LAVA => 2,
+        _ => 1,
+    }
+}
+
+/// How many ticks `fluid` waits to react to a change next to it.
+pub fn update_delay(fluid: Block) -> u64 {
+    match fluid {
+        Block::LAVA => 30,
+        _ => 5,
+    }
+}
+
+/// How high the fluid reaches at `level`, in 16ths of a block.
+/// Falling fluid fills its block, and the further fluid has spread, the shallower it is.
+pub fn height(level: u8) -> u32 {
+    match level {
+        FALLING => 16,
+        level => 14 - level.min(MAX_FLOWING) as u32 * 12 / MAX_FLOWING as u32,
+    }
+}
+
+/// Updates the fluid at `pos`: it dries up or changes level to match what's flowing into it,
+/// then flows down, or sideways if it can't.
+pub fn update(world: &mut World, pos: Vec3<i32>) {
+    let fluid = world.get_block(pos);
+    if !fluid.info().liquid {
+        return;
+    }
+    if fluid == Block::LAVA && harden(world, pos) {
+        return;
+    }
+
+    let mut level = world.fluid_level(pos);
+    if level != SOURCE {
+        match fed_level(world, pos, fluid) {
+            Some(fed) if fed == level => {}
+            Some(fed) => {
+                // the neighbors are updated by the change, so they'll follow along
+                world.set_fluid(pos, fluid, fed);
+                level = fed;
+            }
+            None => {
+                world.set_block(pos, Block::AIR);
+                return;
+            }
+        }
+    }
+
+    if flow_into(world, pos + DOWN, fluid, FALLING) {
+        return;
+    }
+    if !spreads_sideways(world, pos, fluid) {
+        return;
+    }
+    let next = match level {
+        FALLING => level_step(fluid),
+        level => level + level_step(fluid),
+    };
+    if next > MAX_FLOWING {
+        return;
+    }
+    for side in SIDES {
+        flow_into(world, pos + side, fluid, next);
+    }
+}
+
+/// The level the flowing fluid at `pos` should have, from the fluid around it that flows into
+/// it. `None` if nothing does, and it should dry up.
+fn fed_level(world: &World, pos: Vec3<i32>, fluid: Block) -> Option<u8> {
+    if world.get_block(pos + UP) == fluid {
+        return Some(FALLING);
+    }
+    let mut sources = 0;
+    let mut fed = None;
+    for side in SIDES {
+        let neighbor = pos + side;
+        if world.get_block(neighbor) != fluid || !spreads_sideways(world, neighbor, fluid) {
+            continue;
+        }
+        let level = match world.fluid_level(neighbor) {
+            SOURCE => {
+                sources += 1;
+                level_step(fluid)
+            }
+            FALLING => level_step(fluid),
+            level => level + level_step(fluid),
+        };
+        if level <= MAX_FLOWING && fed.is_none_or(|fed| level < fed) {
+            fed = Some(level);
+        }
+    }
+    // water between two sources fills in, so a pool can't be emptied one block at a time
+    if fluid == Block::WATER && sources >= 2 && !can_fall_into(world, pos + DOWN, fluid) {
+        return Some(SOURCE);
+    }
+    fed
+}
+
+/// Whether the fluid at `pos` spreads to its sides. Fluid only spreads once it can't fall any
+/// further, onto solid ground or into a pool.
+fn spreads_sideways(world: &World, pos: Vec3<i32>, fluid: Block) -> bool {
+    !can_fall_into(world, pos + DOWN, fluid)
+}
+
+fn can_fall_into(world: &World, pos: Vec3<i32>, fluid: Block) -> bool {
+    let block = world.get_block(pos);
+    block.is_air() || (block == fluid && world.fluid_level(pos) != SOURCE)
+}
+
+/// Flows `fluid` into `pos` at `level`, if it's loaded and empty, or has the same fluid at a higher level.
+/// Returns whether the fluid went into, or fell into, `pos`.
+fn flow_into(world: &mut World, pos: Vec3<i32>, fluid: Block, level: u8) -> bool {
+    // fluids stop at the edge of the world, instead of falling through the void forever
+    if world.chunk(chunk_pos(pos)).is_none() {
+        return false;
+    }
+    let block = world.get_block(pos);
+    if block.is_air() {
+        world.set_fluid(pos, fluid, level);
+        return true;
+    }
+    if block == fluid {
+        let current = world.fluid_level(pos);
+        let deeper = match (level, current) {
+            (_, SOURCE) => false,
+            (FALLING, current) => current != FALLING,
+            (_, FALLING) => false,
+            (level, current) => level < current,
+        };
+        if deeper {
+            world.set_fluid(pos, fluid, level);
+        }
+        return level == FALLING;
+    }
+    // lava that runs into water cools off. Water that runs into lava is handled by `harden`,
+    // when the lava is updated
+    if fluid == Block::LAVA && block == Block::WATER {
+        world.set_block(pos, Block::STONE);
+        return level == FALLING;
+    }
+    false
+}
+
+/// Turns the lava at `pos` into rock if it's touching water from above or the side.
+/// Sources turn into obsidian, and flowing lava into cobblestone.
+/// Returns whether the lava hardened.
+fn harden(world: &mut World, pos: Vec3<i32>) -> bool {
+    let touching_water =
+        (SIDES.into_iter().chain([UP])).any(|offset| world.get_block(pos + offset) == Block::WATER);
+    if !touching_water {
+        return false;
+    }
+    let rock = match world.fluid_level(pos) {
+        SOURCE => Block::OBSIDIAN,
+        _ => Block::COBBLESTONE,
+    };
+    world.set_block(pos, rock);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ticks until there are no block updates left.
+    fn settle(world: &mut World) {
+        for _ in 0..10_000 {
+            if world.block_updates.is_empty() {
+                return;
+            }
+            world.tick_n(1);
+        }
+        panic!("the fluids never settled");
+    }
+
+    #[test]
+    fn sources_spread_until_they_run_out() {
+        let mut world = World::with_floor(10);
+        world.set_block(Vec3::new(0, 1, 0), Block::WATER);
+        settle(&mut world);
+
+        for x in 0..=MAX_FLOWING as i32 {
+            let pos = Vec3::new(x, 1, 0);
+            assert_eq!(world.get_block(pos), Block::WATER, "{pos:?}");
+            assert_eq!(world.fluid_level(pos), x as u8, "{pos:?}");
+        }
+        // flowing fluid spreads around corners too, a level at a time
+        assert_eq!(world.fluid_level(Vec3::new(3, 1, 2)), 5);
+        assert_eq!(
+            world.get_block(Vec3::new(MAX_FLOWING as i32 + 1, 1, 0)),
+            Block::AIR
+        );
+        assert_eq!(world.get_block(Vec3::new(4, 1, 4)), Block::AIR);
+        // it doesn't climb
+        assert_eq!(world.get_block(Vec3::new(0, 2, 0)), Block::AIR);
+    }
+
+    #[test]
+    fn lava_spreads_less_than_water() {
+        let mut world = World::with_floor(10);
+        world.set_block(Vec3::new(0, 1, 0), Block::LAVA);
+        settle(&mut world);
+
+        assert_eq!(world.fluid_level(Vec3::new(3, 1, 0)), 6);
+        assert_eq!(world.get_block(Vec3::new(4, 1, 0)), Block::AIR);
+    }
+
+    #[test]
+    fn fluids_fall_and_spread_where_they_land() {
+        let mut world = World::with_floor(10);
+        world.set_block(Vec3::new(0, 5, 0), Block::WATER);
+        settle(&mut world);
+
+        for y in 1..5 {
+            let pos = Vec3::new(0, y, 0);
+            assert_eq!(world.get_block(pos), Block::WATER, "{pos:?}");
+            assert_eq!(world.fluid_level(pos), FALLING, "{pos:?}");
+        }
+        // the source is only above the column, so it doesn't spread up there
+        assert_eq!(world.get_block(Vec3::new(1, 5, 0)), Block::AIR);
+        // the bottom of the column spreads like a source
+        assert_eq!(world.fluid_level(Vec3::new(2, 1, 0)), 2);
+    }
+
+    #[test]
+    fn flowing_fluid_recedes_without_a_source() {
+        let mut world = World::with_floor(10);
+        world.set_block(Vec3::new(0, 3, 0), Block::WATER);
+        settle(&mut world);
+        assert_eq!(world.get_block(Vec3::new(5, 1, 0)), Block::WATER);
+
+        world.set_block(Vec3::new(0, 3, 0), Block::AIR);
+        settle(&mut world);
+        for x in -10..=10 {
+            for z in -10..=10 {
+                for y in 1..4 {
+                    assert!(world.get_block(Vec3::new(x, y, z)).is_air());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn water_fills_in_between_sources() {
+        let mut world = World::with_floor(10);
+        world.set_block(Vec3::new(0, 1, 0), Block::WATER);
+        world.set_block(Vec3::new(2, 1, 0), Block::WATER);
+        settle(&mut world);
+        assert_eq!(world.fluid_level(Vec3::new(1, 1, 0)), SOURCE);
+    }
+
+    #[test]
+    fn water_and_lava_harden_into_rock() {
+        // water falling onto a lava source makes obsidian
+        let mut world = World::with_floor(10);
+        world.set_block(Vec3::new(0, 1, 0), Block::LAVA);
+        settle(&mut world);
+        world.set_block(Vec3::new(0, 3, 0), Block::WATER);
+        settle(&mut world);
+        assert_eq!(world.get_block(Vec3::new(0, 1, 0)), Block::OBSIDIAN);
+
+        // and onto flowing lava makes cobblestone
+        let mut world = World::with_floor(10);
+        world.set_block(Vec3::new(0, 1, 0), Block::LAVA);
+        settle(&mut world);
+        world.set_block(Vec3::new(-1, 3, 0), Block::WATER);
+        settle(&mut world);
+        assert_eq!(world.get_block(Vec3::new(-1, 1, 0)), Block::COBBLESTONE);
+
+        // lava falling onto water turns it into stone
+        let mut world = World::with_floor(10);
+        world.set_block(Vec3::new(0, 1, 0), Block::WATER);
+        world.set_block(Vec3::new(0, 5, 0), Block::LAVA);
+        settle(&mut world);
+        assert_eq!(world.get_block(Vec3::new(0, 1, 0)), Block::STONE);
+    }
+
+    #[test]
+    fn fluids_spread_a_block_per_update_delay() {
+        let mut world = World::with_floor(10);
+        world.set_block(Vec3::new(0, 1, 0), Block::WATER);
+        world.set_block(Vec3::new(8, 1, 8), Block::LAVA);
+        let delay = update_delay(Block::WATER);
+
+        world.tick_n(delay);
+        assert_eq!(world.get_block(Vec3::new(1, 1, 0)), Block::AIR);
+        // the update scheduled when the water was placed runs now, and flows into its neighbors
+        world.tick_n(1);
+        assert_eq!(world.get_block(Vec3::new(1, 1, 0)), Block::WATER);
+        assert_eq!(world.get_block(Vec3::new(2, 1, 0)), Block::AIR);
+        world.tick_n(delay);
+        assert_eq!(world.get_block(Vec3::new(2, 1, 0)), Block::WATER);
+        assert_eq!(world.get_block(Vec3::new(7, 1, 8)), Block::AIR);
+
+        world.tick_n(update_delay(Block::LAVA) - 2 * delay);
+        assert_eq!(world.get_block(Vec3::new(7, 1, 8)), Block::LAVA);
+    }
+}
@@ -0,0 +1,93 @@
+//! How grass spreads and dies, on random ticks (see `block_update`).
+
+use crate::block::Block;
+use crate::block_update;
+use crate::light::LightKind;
+use crate::math::Vec3;
+use crate::world::World;
+
+/// The light a block of dirt needs above it for grass to spread onto it.
+pub const MIN_SPREAD_LIGHT: u8 = 9;
+
+/// Whether grass can grow under `block`.
+fn can_grow_under(block: Block) -> bool {
+    !block.info().opaque && !block.info().liquid
+}
+
+/// Turns the grass at `pos` into dirt if it's been covered up. Otherwise spreads it to a random
+/// block of dirt near it, up to a block above or three below, if the dirt is lit enough.
+pub fn random_tick(world: &mut World, pos: Vec3<i32>) {
+    let up = Vec3::new(0, 1, 0);
+    if !can_grow_under(world.get_block(pos + up)) {
+        world.set_block(pos, Block::DIRT);
+        return;
+    }
+
+    let bits = block_update::random(world.seed, world.tick, pos);
+    let offset = |bits: u64, range: u64, min: i32| (bits % range) as i32 + min;
+    let target = pos
+        + Vec3::new(
+            offset(bits, 3, -1),
+            offset(bits >> 8, 5, -3),
+            offset(bits >> 16, 3, -1),
+        );
+    if world.get_block(target) != Block::DIRT || !can_grow_under(world.get_block(target + up)) {
+        return;
+    }
+    let light =
+        [LightKind::Sky, LightKind::Block].map(|kind| world.light(target + up, kind).unwrap_or(0));
+    if light.into_iter().max().unwrap() >= MIN_SPREAD_LIGHT {
+        world.set_block(target, Block::GRASS);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A world with a dirt floor at y = 0, from -8 to 7 on X and Z, and grass in the middle.
+    fn lawn(seed: u64) -> World {
+        let mut world = World::empty(seed);
+        for x in -8..8 {
+            for z in -8..8 {
+                world.set_block(Vec3::new(x, 0, z), Block::DIRT);
+            }
+        }
+        world.set_block(Vec3::new(0, 0, 0), Block::GRASS);
+        world
+    }
+
+    fn grass(world: &World) -> Vec<Vec3<i32>> {
+        let mut grass = Vec::new();
+        for x in -8..8 {
+            for z in -8..8 {
+                let pos = Vec3::new(x, 0, z);
+                if world.get_block(pos) == Block::GRASS {
+                    grass.push(pos);
+                }
+            }
+        }
+        grass
+    }
+
+    #[test]
+    fn grass_spreads_the_same_way_every_time() {
+        let mut a = lawn(3);
+        let mut b = lawn(3);
+        a.tick_n(60_000);
+        b.tick_n(60_000);
+        let spread = grass(&a);
+        assert!(spread.len() > 5, "{spread:?}");
+        assert_eq!(spread, grass(&b));
+    }
+
+    #[test]
+    fn covered_grass_dies() {
+        let mut world = lawn(0);
+        world.set_block(Vec3::new(0, 1, 0), Block::STONE);
+        // without anything else to tick, the grass is picked soon enough
+        world.tick_n(20_000);
+        assert_eq!(world.get_block(Vec3::new(0, 0, 0)), Block::DIRT);
+        assert!(grass(&world).is_empty());
+    }
+}
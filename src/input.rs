@@ -1,14 +1,34 @@
 use crate::math::Vec2;
 use std::collections::HashSet;
 pub use winit::event::VirtualKeyCode as Key;
-use winit::event::{DeviceEvent, ElementState, Event, MouseButton, WindowEvent};
+use winit::event::{DeviceEvent, ElementState, Event, MouseButton, MouseScrollDelta, WindowEvent};
+
+/// How many pixels of a `MouseScrollDelta::PixelDelta` count as one line of scrolling.
+pub const PIXELS_PER_SCROLL_LINE: f64 = 20.0;
 
 pub struct FrameInput {
     pub pressed_keys: HashSet<Key>,
     pub pressed_mouse_buttons: HashSet<MouseButton>,
 
+    // edge-triggered state, only valid for the frame the event happened in.
+    // a key can be in both `just_pressed_keys` and `just_released_keys`,
+    // if it was pressed and released within the same frame
+    pub just_pressed_keys: HashSet<Key>,
+    pub just_released_keys: HashSet<Key>,
+    pub repeated_keys: HashSet<Key>,
+    pub just_pressed_mouse_buttons: HashSet<MouseButton>,
+    pub just_released_mouse_buttons: HashSet<MouseButton>,
+
     pub cursor_change: Vec2<f64>,
+    // in lines, positive Y is scrolling up (away from the user)
     pub scroll_change: Vec2<f64>,
+    // printable characters typed this frame, in the order they were received
+    pub text_input: String,
+}
+impl Default for FrameInput {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 impl FrameInput {
     pub fn new() -> Self {
@@ -16,14 +36,29 @@ impl FrameInput {
             pressed_keys: HashSet::new(),
             pressed_mouse_buttons: HashSet::new(),
 
+            just_pressed_keys: HashSet::new(),
+            just_released_keys: HashSet::new(),
+            repeated_keys: HashSet::new(),
+            just_pressed_mouse_buttons: HashSet::new(),
+            just_released_mouse_buttons: HashSet::new(),
+
             cursor_change: Vec2::all(0.0),
             scroll_change: Vec2::all(0.0),
+            text_input: String::new(),
         }
     }
 
+    /// Resets all per-frame state. Held keys and buttons are kept.
     pub fn clear(&mut self) {
+        self.just_pressed_keys.clear();
+        self.just_released_keys.clear();
+        self.repeated_keys.clear();
+        self.just_pressed_mouse_buttons.clear();
+        self.just_released_mouse_buttons.clear();
+
         self.cursor_change = Vec2::all(0.0);
         self.scroll_change = Vec2::all(0.0);
+        self.text_input.clear();
     }
 
     #[inline(always)]
@@ -35,39 +70,169 @@ impl FrameInput {
         self.scroll_change
     }
 
+    #[inline(always)]
+    pub fn text_input(&self) -> &str {
+        &self.text_input
+    }
+
     #[inline(always)]
     pub fn key_pressed(&self, key: Key) -> bool {
         self.pressed_keys.contains(&key)
     }
+    /// Returns true if `key` went down this frame. Key repeats don't count.
+    #[inline(always)]
+    pub fn key_just_pressed(&self, key: Key) -> bool {
+        self.just_pressed_keys.contains(&key)
+    }
+    #[inline(always)]
+    pub fn key_just_released(&self, key: Key) -> bool {
+        self.just_released_keys.contains(&key)
+    }
+    /// Returns true if the OS sent a repeat for `key` this frame, while it was held.
+    #[inline(always)]
+    pub fn key_repeated(&self, key: Key) -> bool {
+        self.repeated_keys.contains(&key)
+    }
+    /// Returns true if `key` went down, or was repeated, this frame.
+    /// Useful for text fields and menus, where holding a key should repeat the action.
+    #[inline(always)]
+    pub fn key_typed(&self, key: Key) -> bool {
+        self.key_just_pressed(key) || self.key_repeated(key)
+    }
+
     #[inline(always)]
     pub fn mouse_button_pressed(&self, button: MouseButton) -> bool {
         self.pressed_mouse_buttons.contains(&button)
     }
+    #[inline(always)]
+    pub fn mouse_button_just_pressed(&self, button: MouseButton) -> bool {
+        self.just_pressed_mouse_buttons.contains(&button)
+    }
+    #[inline(always)]
+    pub fn mouse_button_just_released(&self, button: MouseButton) -> bool {
+        self.just_released_mouse_buttons.contains(&button)
+    }
+
+    pub fn press_key(&mut self, key: Key) {
+        if self.pressed_keys.insert(key) {
+            self.just_pressed_keys.insert(key);
+        } else {
+            // a press for a key that is already held is an OS key repeat
+            self.repeated_keys.insert(key);
+        }
+    }
+    pub fn release_key(&mut self, key: Key) {
+        if self.pressed_keys.remove(&key) {
+            self.just_released_keys.insert(key);
+        }
+    }
+
+    /// Releases every held key and mouse button, as if the user let go of all of them.
+    /// Used when the window loses focus, since we won't receive the release events.
+    pub fn release_all(&mut self) {
+        self.just_released_keys.extend(self.pressed_keys.drain());
+        self.just_released_mouse_buttons
+            .extend(self.pressed_mouse_buttons.drain());
+    }
+
+    pub fn press_mouse_button(&mut self, button: MouseButton) {
+        if self.pressed_mouse_buttons.insert(button) {
+            self.just_pressed_mouse_buttons.insert(button);
+        }
+    }
+    pub fn release_mouse_button(&mut self, button: MouseButton) {
+        if self.pressed_mouse_buttons.remove(&button) {
+            self.just_released_mouse_buttons.insert(button);
+        }
+    }
+
+    pub fn scroll(&mut self, delta: MouseScrollDelta) {
+        match delta {
+            MouseScrollDelta::LineDelta(x, y) => {
+                self.scroll_change.x += x as f64;
+                self.scroll_change.y += y as f64;
+            }
+            MouseScrollDelta::PixelDelta(pos) => {
+                self.scroll_change.x += pos.x / PIXELS_PER_SCROLL_LINE;
+                self.scroll_change.y += pos.y / PIXELS_PER_SCROLL_LINE;
+            }
+        }
+    }
+
+    pub fn receive_char(&mut self, c: char) {
+        // control characters (backspace, enter, escape, ...) are handled through key events
+        if !c.is_control() {
+            self.text_input.push(c);
+        }
+    }
 
     pub fn capture_event(&mut self, event: &Event<()>) -> bool {
         match event {
-            Event::WindowEvent { event, .. } => match event {
-                WindowEvent::KeyboardInput { input, .. } => {
-                    if let Some(key) = input.virtual_keycode {
-                        if input.state == ElementState::Pressed {
-                            self.pressed_keys.insert(key);
-                        } else {
-                            self.pressed_keys.remove(&key);
-                        }
+            Event::WindowEvent {
+                event: WindowEvent::KeyboardInput { input, .. },
+                ..
+            } => {
+                if let Some(key) = input.virtual_keycode {
+                    match input.state {
+                        ElementState::Pressed => self.press_key(key),
+                        ElementState::Released => self.release_key(key),
                     }
-                    true
                 }
-                _ => false,
-            },
-            Event::DeviceEvent { event, .. } => match event {
-                DeviceEvent::MouseMotion { delta, .. } => {
-                    self.cursor_change.x += delta.0;
-                    self.cursor_change.y += delta.1;
-                    true
+                true
+            }
+            Event::WindowEvent {
+                event: WindowEvent::MouseInput { state, button, .. },
+                ..
+            } => {
+                match state {
+                    ElementState::Pressed => self.press_mouse_button(*button),
+                    ElementState::Released => self.release_mouse_button(*button),
                 }
-                _ => false,
-            },
+                true
+            }
+            Event::WindowEvent {
+                event: WindowEvent::MouseWheel { delta, .. },
+                ..
+            } => {
+                self.scroll(*delta);
+                true
+            }
+            Event::WindowEvent {
+                event: WindowEvent::ReceivedCharacter(c),
+                ..
+            } => {
+                self.receive_char(*c);
+                true
+            }
+            Event::DeviceEvent {
+                event: DeviceEvent::MouseMotion { delta, .. },
+                ..
+            } => {
+                self.cursor_change.x += delta.0;
+                self.cursor_change.y += delta.1;
+                true
+            }
             _ => false,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn press_and_release_within_one_frame() {
+        let mut input = FrameInput::new();
+        input.press_key(Key::E);
+        input.release_key(Key::E);
+        assert!(input.key_just_pressed(Key::E));
+        assert!(input.key_just_released(Key::E));
+        assert!(!input.key_pressed(Key::E));
+        assert!(!input.key_repeated(Key::E));
+
+        input.clear();
+        assert!(!input.key_just_pressed(Key::E));
+        assert!(!input.key_just_released(Key::E));
+    }
+}
@@ -0,0 +1,161 @@
+//! Drawing many copies of the same model, like entities, with one draw call per model.
+//!
+//! Models are registered once with `InstanceRenderer::add_model`. Every frame, instances of them
+//! are submitted in batches, then uploaded together into a single instance buffer and drawn with
+//! one `draw_indexed` per model.
+
+use crate::math::Mat4;
+use crate::model::ModelMesh;
+use std::ops::Range;
+
+/// The per-instance data of a model, in a second vertex buffer next to the model's vertices.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Instance {
+    // from model space to world space, like `Mat4::transformation` makes
+    pub transform: Mat4,
+    // multiplies the color of the model
+    pub color: [f32; 4],
+}
+impl Instance {
+    pub const WHITE: [f32; 4] = [1.0; 4];
+
+    #[inline(always)]
+    pub const fn new(transform: Mat4, color: [f32; 4]) -> Self {
+        Self { transform, color }
+    }
+
+    // the matrix takes up one location per column, after the locations of `model::Vertex`
+    pub const ATTRIBS: [wgpu::VertexAttribute; 5] = wgpu::vertex_attr_array![
+        3 => Float32x4, 4 => Float32x4, 5 => Float32x4, 6 => Float32x4, 7 => Float32x4
+    ];
+
+    pub fn buffer_layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Self>() as u64,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBS,
+        }
+    }
+}
+
+/// A model registered with an `InstanceRenderer`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ModelId(usize);
+
+pub struct InstanceRenderer {
+    // uses `Instance::buffer_layout` as its second vertex buffer
+    pipeline: wgpu::RenderPipeline,
+    models: Vec<ModelMesh>,
+    // the instances submitted this frame, indexed by `ModelId`
+    batches: Vec<Vec<Instance>>,
+    // every batch, one after the other. Reused across frames, and replaced when it's too small
+    instance_buffer: wgpu::Buffer,
+    instance_capacity: usize,
+    // where each model's batch is in `instance_buffer`, indexed by `ModelId`
+    ranges: Vec<Range<u32>>,
+}
+impl InstanceRenderer {
+    pub fn new(device: &wgpu::Device, pipeline: wgpu::RenderPipeline) -> Self {
+        const INITIAL_CAPACITY: usize = 64;
+        Self {
+            pipeline,
+            models: Vec::new(),
+            batches: Vec::new(),
+            instance_buffer: Self::create_instance_buffer(device, INITIAL_CAPACITY),
+            instance_capacity: INITIAL_CAPACITY,
+            ranges: Vec::new(),
+        }
+    }
+
+    fn create_instance_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("instance buffer"),
+            size: (capacity * std::mem::size_of::<Instance>()) as u64,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    pub fn add_model(&mut self, model: ModelMesh) -> ModelId {
+        self.models.push(model);
+        self.batches.push(Vec::new());
+        self.ranges.push(0..0);
+        ModelId(self.models.len() - 1)
+    }
+
+    /// Queues `instances` of `model` to be drawn this frame.
+    pub fn submit(&mut self, model: ModelId, instances: impl IntoIterator<Item = Instance>) {
+        self.batches[model.0].extend(instances);
+    }
+
+    /// Uploads the instances submitted since the last frame, and clears them for the next one.
+    pub fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        let (instances, ranges) = pack(&mut self.batches);
+        self.ranges = ranges;
+        if instances.is_empty() {
+            return;
+        }
+        if instances.len() > self.instance_capacity {
+            self.instance_capacity = instances.len().next_power_of_two();
+            self.instance_buffer = Self::create_instance_buffer(device, self.instance_capacity);
+        }
+        queue.write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(&instances));
+    }
+
+    /// Draws the instances uploaded by `prepare`, with one draw per model.
+    /// Expects the texture and camera bind groups to be set already.
+    pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        if self.ranges.iter().all(Range::is_empty) {
+            return;
+        }
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+        for (model, instances) in self.models.iter().zip(&self.ranges) {
+            if instances.is_empty() {
+                continue;
+            }
+            render_pass.set_vertex_buffer(0, model.vertex_buffer.slice(..));
+            render_pass.set_index_buffer(model.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+            render_pass.draw_indexed(0..model.num_indices, 0, instances.clone());
+        }
+    }
+}
+
+/// Puts the instances of every batch one after the other, emptying the batches.
+/// Returns the instances, and the range of each batch in them.
+fn pack(batches: &mut [Vec<Instance>]) -> (Vec<Instance>, Vec<Range<u32>>) {
+    let mut instances = Vec::with_capacity(batches.iter().map(Vec::len).sum());
+    let ranges = (batches.iter_mut())
+        .map(|batch| {
+            let start = instances.len() as u32;
+            instances.append(batch);
+            start..instances.len() as u32
+        })
+        .collect();
+    (instances, ranges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Vec3;
+
+    #[test]
+    fn batches_are_packed_one_after_the_other() {
+        let instance = |x: f32| Instance::new(Mat4::translation(Vec3::new(x, 0.0, 0.0)), [x; 4]);
+        let mut batches = vec![
+            vec![instance(0.0), instance(1.0)],
+            vec![],
+            vec![instance(2.0), instance(3.0), instance(4.0)],
+        ];
+        let (instances, ranges) = pack(&mut batches);
+
+        assert_eq!(ranges, [0..2, 2..2, 2..5]);
+        for (i, instance) in instances.iter().enumerate() {
+            assert_eq!(instance.color[0], i as f32);
+            assert_eq!(instance.transform.0[3][0], i as f32);
+        }
+        assert!(batches.iter().all(Vec::is_empty));
+    }
+}
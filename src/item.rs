@@ -0,0 +1,54 @@
+//! Blocks lying around as items, like the block a thrown projectile turns into when it lands.
+//!
+//! Items are small spinning copies of their block. There's no inventory to pick them up into
+//! yet, so they disappear after a while instead.
+
+use crate::block::Block;
+use crate::entity::{Entity, EntityKind, EntityModel};
+use crate::math::Vec3;
+use crate::world::TICKS_PER_SECOND;
+
+/// The size of an item.
+pub const SIZE: f32 = 0.25;
+/// How fast items spin about the Y axis, in degrees per tick.
+pub const SPIN_SPEED: f32 = 3.0;
+/// How many ticks an item lasts, before it disappears.
+pub const LIFETIME: u32 = 5 * 60 * TICKS_PER_SECOND;
+
+/// An item of `block`, with the center of its bottom at `pos`.
+pub fn entity(block: Block, pos: Vec3<f32>) -> Entity {
+    let kind = EntityKind::Item(block);
+    Entity::new(kind, EntityModel::Block(block), pos, Vec3::all(SIZE))
+}
+
+/// Spins the item. Returns whether it's still around.
+pub fn update_entity(entity: &mut Entity) -> bool {
+    entity.turn(Vec3::new(0.0, 1.0, 0.0), SPIN_SPEED);
+    entity.age < LIFETIME
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::World;
+
+    #[test]
+    fn items_spin_and_disappear() {
+        let mut world = World::with_floor(4);
+        world
+            .entities
+            .push(entity(Block::DIRT, Vec3::new(0.5, 3.0, 0.5)));
+        world.tick_n(60);
+        let item = &world.entities[0];
+        assert!(item.on_ground);
+        assert_eq!(item.pos.y, 1.0);
+        // turned about Y, so +X ends up pointing away from the Y axis at the spin angle
+        let angle = (SPIN_SPEED * 60.0).to_radians();
+        let x = item.orientation.rotate(Vec3::new(1.0, 0.0, 0.0));
+        let expected = Vec3::new(angle.cos(), 0.0, -angle.sin());
+        assert!((x - expected).len() < 1e-4, "{x:?} != {expected:?}");
+
+        world.tick_n(LIFETIME as u64 - 60);
+        assert!(world.entities.is_empty());
+    }
+}
@@ -1,22 +1,54 @@
 #![allow(dead_code)]
 
+pub mod block;
+pub mod block_update;
 pub mod cam;
+pub mod chunk;
+pub mod collision;
+pub mod console;
+pub mod entity;
+pub mod falling_block;
+pub mod fluid;
+pub mod grass;
 pub mod input;
+pub mod instancing;
+pub mod item;
+pub mod light;
 pub mod math;
+pub mod mesher;
 pub mod model;
 pub mod player;
+pub mod projectile;
+pub mod replay;
+pub mod settings;
+pub mod sky;
 pub mod texture;
-
-use crate::input::FrameInput;
+pub mod world;
+pub mod worldgen;
+
+use crate::block::RenderLayer;
+use crate::cam::Cam;
+use crate::chunk::{chunk_origin, chunk_pos, CHUNK_SIZE};
+use crate::entity::EntityModel;
+use crate::input::{FrameInput, Key};
+use crate::instancing::{Instance, InstanceRenderer, ModelId};
 use crate::math::{Mat4, Vec3};
-use crate::player::Player;
+use crate::mesher::{ChunkLayers, ChunkMesh, ChunkMeshData, PackedVertex};
+use crate::model::ModelMesh;
+use crate::replay::Recording;
+use crate::settings::Settings;
+use crate::sky::{SkyRenderer, SkyUniform};
+use crate::world::{World, TICKS_PER_SECOND, TICK_DURATION};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Instant;
 use texture::{Texture, DEPTH_TEXTURE_FORMAT};
 use wgpu::util::DeviceExt;
 use winit::{
     dpi::PhysicalSize,
     event::*,
     event_loop::{ControlFlow, EventLoop},
-    window::{Window, WindowBuilder},
+    window::{CursorGrabMode, Window, WindowBuilder},
 };
 
 pub struct Uniform<T> {
@@ -37,7 +69,7 @@ impl<T: bytemuck::NoUninit> Uniform<T> {
         queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[self.data]));
     }
 
-    pub fn binding(&self, binding: u32) -> wgpu::BindGroupEntry {
+    pub fn binding(&self, binding: u32) -> wgpu::BindGroupEntry<'_> {
         wgpu::BindGroupEntry {
             binding,
             resource: self.buffer.as_entire_binding(),
@@ -48,16 +80,24 @@ impl<T: bytemuck::NoUninit> Uniform<T> {
         binding: u32,
         visibility: wgpu::ShaderStages,
     ) -> wgpu::BindGroupLayoutEntry {
-        wgpu::BindGroupLayoutEntry {
-            binding,
-            visibility,
-            ty: wgpu::BindingType::Buffer {
-                ty: wgpu::BufferBindingType::Uniform,
-                has_dynamic_offset: false,
-                min_binding_size: None,
-            },
-            count: None,
-        }
+        uniform_bind_layout(binding, visibility)
+    }
+}
+
+/// The layout entry of a `Uniform`, for layouts made before there's a uniform to ask.
+pub fn uniform_bind_layout(
+    binding: u32,
+    visibility: wgpu::ShaderStages,
+) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
     }
 }
 
@@ -66,22 +106,200 @@ struct Projection {
     aspect: f32,
     near: f32,
     far: f32,
+    reverse_z: bool,
 }
 impl Projection {
-    pub fn default(aspect: f32) -> Self {
+    pub fn new(settings: &Settings, aspect: f32) -> Self {
         Self {
-            fov: 70.0,
+            fov: settings.fov,
             aspect,
             near: 0.01,
-            far: 1000.0,
+            // reversed depth is precise enough to push the far plane out to infinity
+            far: if settings.reverse_z {
+                f32::INFINITY
+            } else {
+                1000.0
+            },
+            reverse_z: settings.reverse_z,
         }
     }
 
     pub fn matrix(&self) -> Mat4 {
-        Mat4::projection(self.fov.to_radians(), self.aspect, self.near, self.far)
+        let fov = self.fov.to_radians();
+        match (self.reverse_z, self.far.is_infinite()) {
+            (false, false) => Mat4::projection(fov, self.aspect, self.near, self.far),
+            (false, true) => Mat4::projection_infinite(fov, self.aspect, self.near),
+            (true, false) => Mat4::projection_reverse_z(fov, self.aspect, self.near, self.far),
+            (true, true) => Mat4::projection_infinite_reverse_z(fov, self.aspect, self.near),
+        }
+    }
+
+    /// The depth compare function that lets closer fragments pass.
+    pub fn depth_compare(&self) -> wgpu::CompareFunction {
+        if self.reverse_z {
+            wgpu::CompareFunction::Greater
+        } else {
+            wgpu::CompareFunction::Less
+        }
+    }
+
+    /// The depth of a fragment infinitely far away, which the depth buffer is cleared to.
+    pub fn depth_clear(&self) -> f32 {
+        if self.reverse_z {
+            0.0
+        } else {
+            1.0
+        }
+    }
+}
+
+/// The lighting parameters shared by every fragment, laid out to match `Lighting` in `shader.wgsl`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Lighting {
+    // normalized direction pointing towards the sun
+    pub sun_dir: [f32; 3],
+    // how bright surfaces facing away from the sun are, 0..1
+    pub ambient: f32,
+    pub sun_color: [f32; 3],
+    // how bright sky light is, 0..1. Lower at night
+    pub sky_light: f32,
+}
+impl Lighting {
+    pub fn new(sun_dir: Vec3<f32>, sun_color: [f32; 3], ambient: f32, sky_light: f32) -> Self {
+        Self {
+            sun_dir: sun_dir.norm().into(),
+            ambient,
+            sun_color,
+            sky_light,
+        }
+    }
+}
+impl Default for Lighting {
+    fn default() -> Self {
+        // the sun is a little off from straight up, so that every side of a block is lit differently
+        Self::new(Vec3::new(0.4, 1.0, 0.3), [1.0, 1.0, 0.95], 0.45, 1.0)
+    }
+}
+
+/// How things fade into the distance, laid out to match `Fog` in `shader.wgsl`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Fog {
+    // the color things fade to
+    pub color: [f32; 3],
+    // when above 0, the fog is exponential with this density, per block.
+    // Otherwise it's linear between `start` and `end`
+    pub density: f32,
+    pub start: f32,
+    pub end: f32,
+    _padding: [f32; 2],
+}
+impl Fog {
+    /// Fog that starts at `start` blocks away, and completely hides things `end` blocks away.
+    pub fn linear(color: [f32; 3], start: f32, end: f32) -> Self {
+        Self {
+            color,
+            density: 0.0,
+            start,
+            end,
+            _padding: [0.0; 2],
+        }
+    }
+
+    /// Fog that thickens quickly up close, and never quite hides anything.
+    pub fn exponential(color: [f32; 3], density: f32) -> Self {
+        Self {
+            color,
+            density,
+            start: 0.0,
+            end: 0.0,
+            _padding: [0.0; 2],
+        }
+    }
+
+    /// How much of a point `dist` blocks away is hidden by the fog, 0..1.
+    /// The same as `fog_amount` in the shaders.
+    pub fn amount(&self, dist: f32) -> f32 {
+        if self.density > 0.0 {
+            let d = dist * self.density;
+            1.0 - (-d * d).exp()
+        } else {
+            ((dist - self.start) / (self.end - self.start)).clamp(0.0, 1.0)
+        }
     }
 }
 
+/// Creates a pipeline that draws the triangles of `layer` with `vs_main` and `fs_main` from
+/// `shader`. The cutout layer uses `fs_cutout` instead, and water uses `vs_water` and `fs_water`.
+#[allow(clippy::too_many_arguments)]
+fn create_render_pipeline(
+    device: &wgpu::Device,
+    label: &str,
+    bind_group_layouts: &[&wgpu::BindGroupLayout],
+    shader: &wgpu::ShaderModule,
+    // the shader's vertex entry point, which has to take `vertex_layouts`
+    vertex_entry_point: &str,
+    vertex_layouts: &[wgpu::VertexBufferLayout],
+    format: wgpu::TextureFormat,
+    depth_compare: wgpu::CompareFunction,
+    layer: RenderLayer,
+) -> wgpu::RenderPipeline {
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some(label),
+        bind_group_layouts,
+        push_constant_ranges: &[],
+    });
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(label),
+        layout: Some(&layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: vertex_entry_point,
+            buffers: vertex_layouts,
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: match layer {
+                RenderLayer::Cutout => "fs_cutout",
+                RenderLayer::Water => "fs_water",
+                _ => "fs_main",
+            },
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(match layer.is_blended() {
+                    true => wgpu::BlendState::ALPHA_BLENDING,
+                    false => wgpu::BlendState::REPLACE,
+                }),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: Some(wgpu::Face::Back),
+            polygon_mode: wgpu::PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: DEPTH_TEXTURE_FORMAT,
+            // blended faces are sorted instead, so the ones behind them aren't hidden
+            depth_write_enabled: !layer.is_blended(),
+            depth_compare,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState {
+            count: 1,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+    })
+}
+
 struct State {
     surface: wgpu::Surface,
     device: wgpu::Device,
@@ -89,21 +307,39 @@ struct State {
     config: wgpu::SurfaceConfiguration,
     size: PhysicalSize<u32>,
     render_pipeline: wgpu::RenderPipeline,
+    // one for each `RenderLayer`
+    chunk_pipelines: ChunkLayers<wgpu::RenderPipeline>,
     depth_texture: Texture,
     vertex_buffer: wgpu::Buffer,
     index_buffer: wgpu::Buffer,
 
     bind_group0: wgpu::BindGroup,
     bind_group1: wgpu::BindGroup,
+    block_atlas_bind_group: wgpu::BindGroup,
+    chunk_bind_group_layout: wgpu::BindGroupLayout,
+    chunk_meshes: HashMap<Vec3<i32>, ChunkMesh>,
+    // draws entities, and anything else there are many copies of
+    instances: InstanceRenderer,
+    // added to `instances` the first time an entity with the model is drawn
+    entity_models: HashMap<EntityModel, ModelId>,
 
     texture: Texture,
-    player: Player,
+    block_atlas: Texture,
+    world: World,
+    // the player's camera before the last tick, for interpolating between ticks
+    prev_cam: Cam,
+    settings: Settings,
+    sky: SkyRenderer,
     projection: Projection,
     view_mat_uniform: Uniform<Mat4>,
     proj_mat_uniform: Uniform<Mat4>,
+    lighting_uniform: Uniform<Lighting>,
+    fog_uniform: Uniform<Fog>,
+    // the time in seconds in x, for animations. The rest is unused
+    time_uniform: Uniform<[f32; 4]>,
 }
 impl State {
-    async fn new(window: &Window) -> Self {
+    async fn new(window: &Window, world: World, settings: Settings) -> Self {
         let size = window.inner_size();
 
         let instance = wgpu::Instance::new(wgpu::Backends::all());
@@ -150,18 +386,35 @@ impl State {
         // Now create textures
 
         let image = image::load_from_memory(include_bytes!("../res/happy-tree.png")).unwrap();
-        let texture = Texture::from_image(&device, &queue, &image, Some("texture"));
+        let texture = Texture::from_image(
+            &device,
+            &queue,
+            &image,
+            wgpu::FilterMode::Linear,
+            Some("texture"),
+        );
+        // sampled without filtering, so the tiles stay crisp and don't bleed into each other
+        let image = image::load_from_memory(include_bytes!("../res/blocks.png")).unwrap();
+        let block_atlas = Texture::from_image(
+            &device,
+            &queue,
+            &image,
+            wgpu::FilterMode::Nearest,
+            Some("block_atlas"),
+        );
 
         // Create uniforms
-        let player = Player::new(Vec3::new(0.0, 0.0, 4.0), Vec3::all(0.0));
         let projection = {
             let win_size = window.inner_size();
             let aspect = win_size.width as f32 / win_size.height as f32;
-            Projection::default(aspect)
+            Projection::new(&settings, aspect)
         };
 
-        let view_mat_uniform = Uniform::new(&device, player.cam.matrix());
+        let view_mat_uniform = Uniform::new(&device, world.player.cam.matrix());
         let proj_mat_uniform = Uniform::new(&device, projection.matrix());
+        let lighting_uniform = Uniform::new(&device, sky::lighting(world.time));
+        let fog_uniform = Uniform::new(&device, Fog::linear([0.0; 3], 0.0, 1.0));
+        let time_uniform = Uniform::new(&device, [0.0; 4]);
 
         // Create bind groups
         let bind_group0_layout =
@@ -174,72 +427,107 @@ impl State {
             layout: &bind_group0_layout,
             entries: &[texture.binding(0), texture.sampler_binding(1)],
         });
+        let block_atlas_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("block_atlas_bind_group"),
+            layout: &bind_group0_layout,
+            entries: &[block_atlas.binding(0), block_atlas.sampler_binding(1)],
+        });
 
         let bind_group1_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 label: Some("bind_group1_layout"),
                 entries: &[
-                    view_mat_uniform.bind_layout(0, wgpu::ShaderStages::VERTEX),
+                    // the water shader uses it for reflections
+                    view_mat_uniform
+                        .bind_layout(0, wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT),
                     proj_mat_uniform.bind_layout(1, wgpu::ShaderStages::VERTEX),
+                    lighting_uniform.bind_layout(2, wgpu::ShaderStages::FRAGMENT),
+                    fog_uniform.bind_layout(3, wgpu::ShaderStages::FRAGMENT),
+                    time_uniform
+                        .bind_layout(4, wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT),
                 ],
             });
         let bind_group1 = device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("bind_group1"),
             layout: &bind_group1_layout,
-            entries: &[view_mat_uniform.binding(0), proj_mat_uniform.binding(1)],
+            entries: &[
+                view_mat_uniform.binding(0),
+                proj_mat_uniform.binding(1),
+                lighting_uniform.binding(2),
+                fog_uniform.binding(3),
+                time_uniform.binding(4),
+            ],
         });
 
         // Create shaders
         let shader = device.create_shader_module(wgpu::include_wgsl!("../res/shader.wgsl"));
-
-        let render_pipeline_layout =
-            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                label: Some("render_pipeline_layout"),
-                bind_group_layouts: &[&bind_group0_layout, &bind_group1_layout],
-                push_constant_ranges: &[],
-            });
-
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("render_pipeline"),
-            layout: Some(&render_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: "vs_main",
-                buffers: &[model::Vertex::buffer_layout()],
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: "fs_main",
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState::REPLACE),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                // cull_mode: Some(wgpu::Face::Back),
-                cull_mode: None,
-                polygon_mode: wgpu::PolygonMode::Fill,
-                unclipped_depth: false,
-                conservative: false,
-            },
-            depth_stencil: Some(wgpu::DepthStencilState {
-                format: DEPTH_TEXTURE_FORMAT,
-                depth_write_enabled: true,
-                depth_compare: wgpu::CompareFunction::Less,
-                stencil: wgpu::StencilState::default(),
-                bias: wgpu::DepthBiasState::default(),
-            }),
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-            multiview: None,
+        let render_pipeline = create_render_pipeline(
+            &device,
+            "render_pipeline",
+            &[&bind_group0_layout, &bind_group1_layout],
+            &shader,
+            "vs_main",
+            &[model::Vertex::buffer_layout()],
+            config.format,
+            projection.depth_compare(),
+            RenderLayer::Opaque,
+        );
+        let instance_pipeline = create_render_pipeline(
+            &device,
+            "instance_pipeline",
+            &[&bind_group0_layout, &bind_group1_layout],
+            &shader,
+            "vs_instanced",
+            &[model::Vertex::buffer_layout(), Instance::buffer_layout()],
+            config.format,
+            projection.depth_compare(),
+            RenderLayer::Opaque,
+        );
+        let instances = InstanceRenderer::new(&device, instance_pipeline);
+
+        let chunk_shader = device.create_shader_module(wgpu::include_wgsl!("../res/chunk.wgsl"));
+        // the water shader builds on the chunk shader, for its vertex format and lighting
+        let water_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("water.wgsl"),
+            source: wgpu::ShaderSource::Wgsl(
+                concat!(
+                    include_str!("../res/chunk.wgsl"),
+                    include_str!("../res/water.wgsl")
+                )
+                .into(),
+            ),
         });
+        let chunk_bind_group_layout = ChunkMesh::bind_group_layout(&device);
+        let chunk_pipelines = RenderLayer::ALL.map(|layer| {
+            create_render_pipeline(
+                &device,
+                &format!("chunk_pipeline_{layer:?}"),
+                &[
+                    &bind_group0_layout,
+                    &bind_group1_layout,
+                    &chunk_bind_group_layout,
+                ],
+                match layer {
+                    RenderLayer::Water => &water_shader,
+                    _ => &chunk_shader,
+                },
+                match layer {
+                    RenderLayer::Water => "vs_water",
+                    _ => "vs_main",
+                },
+                &[PackedVertex::buffer_layout()],
+                config.format,
+                projection.depth_compare(),
+                layer,
+            )
+        });
+
+        let sky = SkyRenderer::new(
+            &device,
+            &queue,
+            config.format,
+            SkyUniform::new(world.time, world.player.cam.matrix(), projection.matrix()),
+        );
 
         // Create models
         let (vertices, indices) = model::default_model();
@@ -262,6 +550,7 @@ impl State {
             config,
             size,
             render_pipeline,
+            chunk_pipelines,
             depth_texture,
 
             vertex_buffer,
@@ -269,12 +558,24 @@ impl State {
 
             bind_group0,
             bind_group1,
+            block_atlas_bind_group,
+            chunk_bind_group_layout,
+            chunk_meshes: HashMap::new(),
+            instances,
+            entity_models: HashMap::new(),
 
             texture,
-            player,
+            block_atlas,
+            prev_cam: world.player.cam.clone(),
+            sky,
+            world,
+            settings,
             projection,
             view_mat_uniform,
             proj_mat_uniform,
+            lighting_uniform,
+            fog_uniform,
+            time_uniform,
         }
     }
 
@@ -296,12 +597,78 @@ impl State {
     }
 
     fn update(&mut self, input: &FrameInput) {
-        self.player.update(input);
-        self.view_mat_uniform.data = self.player.cam.matrix();
-        self.view_mat_uniform.write(&mut self.queue);
+        self.prev_cam = self.world.player.cam.clone();
+        self.world.tick(input);
     }
 
-    fn render(&mut self, _input: &FrameInput) -> Result<(), wgpu::SurfaceError> {
+    /// Rebuilds the meshes of the chunks that changed since the last frame.
+    fn update_chunk_meshes(&mut self) {
+        for chunk_pos in std::mem::take(&mut self.world.dirty_chunks) {
+            let data = mesher::mesh_chunk(&self.world, chunk_pos, &self.settings);
+            if data.iter().all(ChunkMeshData::is_empty) {
+                self.chunk_meshes.remove(&chunk_pos);
+                continue;
+            }
+            let mesh = ChunkMesh::new(&self.device, &self.chunk_bind_group_layout, chunk_pos, data);
+            self.chunk_meshes.insert(chunk_pos, mesh);
+        }
+    }
+
+    /// Submits an instance of the model of every entity, part way between where it was on the
+    /// last tick and where it is now. Models are added the first time they're drawn.
+    fn submit_entities(&mut self, tick_progress: f32) {
+        for entity in &self.world.entities {
+            let model = *self.entity_models.entry(entity.model).or_insert_with(|| {
+                let vertices = match entity.model {
+                    EntityModel::Block(block) => model::block_model(block),
+                };
+                let (_, indices) = model::default_model();
+                let label = format!("{:?}", entity.model);
+                let mesh = ModelMesh::new(&self.device, &label, &vertices, &indices);
+                self.instances.add_model(mesh)
+            });
+            let instance = Instance::new(entity.transformation(tick_progress), entity.color);
+            self.instances.submit(model, [instance]);
+        }
+    }
+
+    /// `tick_progress` is how far (0..1) we are between the last tick and the next one.
+    fn render(
+        &mut self,
+        _input: &FrameInput,
+        tick_progress: f32,
+    ) -> Result<(), wgpu::SurfaceError> {
+        let cam = &self.world.player.cam;
+        self.view_mat_uniform.data = cam.interpolated_matrix(&self.prev_cam, tick_progress);
+        self.view_mat_uniform.write(&mut self.queue);
+        // wrapped, so it stays precise enough for smooth animation
+        let ticks = self.world.tick % (TICKS_PER_SECOND as u64 * ANIMATION_PERIOD);
+        let seconds = (ticks as f32 + tick_progress) / TICKS_PER_SECOND as f32;
+        self.time_uniform.data = [seconds, 0.0, 0.0, 0.0];
+        self.time_uniform.write(&mut self.queue);
+        self.lighting_uniform.data = sky::lighting(self.world.time);
+        self.lighting_uniform.write(&mut self.queue);
+
+        let view_distance = (self.settings.render_distance as i32 * CHUNK_SIZE) as f32;
+        let cam_pos = cam.pos;
+        let cam_block = self.world.get_block(cam_pos.floor_i32());
+        let liquid = Some(cam_block).filter(|block| block.info().liquid);
+        self.fog_uniform.data = sky::fog(self.world.time, view_distance, liquid);
+        self.fog_uniform.write(&mut self.queue);
+        let fog_color = self.fog_uniform.data.color;
+        self.sky.uniform.data = SkyUniform::new(
+            self.world.time,
+            self.view_mat_uniform.data,
+            self.proj_mat_uniform.data,
+        );
+        self.sky.uniform.write(&mut self.queue);
+        self.update_chunk_meshes();
+        for mesh in self.chunk_meshes.values_mut() {
+            mesh.sort_blended(&self.queue, cam_pos);
+        }
+        self.submit_entities(tick_progress);
+        self.instances.prepare(&self.device, &self.queue);
+
         let output = self.surface.get_current_texture()?;
         let view = output
             .texture
@@ -319,10 +686,11 @@ impl State {
                 view: &view,
                 resolve_target: None,
                 ops: wgpu::Operations {
+                    // every pixel is drawn over by the sky, unless it's hidden by a liquid
                     load: wgpu::LoadOp::Clear(wgpu::Color {
-                        r: 0.1,
-                        g: 0.2,
-                        b: 0.3,
+                        r: fog_color[0] as f64,
+                        g: fog_color[1] as f64,
+                        b: fog_color[2] as f64,
                         a: 1.0,
                     }),
                     store: true,
@@ -331,20 +699,66 @@ impl State {
             depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
                 view: &self.depth_texture.view,
                 depth_ops: Some(wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(1.0),
+                    load: wgpu::LoadOp::Clear(self.projection.depth_clear()),
                     store: true,
                 }),
                 stencil_ops: None,
             }),
         });
-        render_pass.set_pipeline(&self.render_pipeline);
+        if liquid.is_none() {
+            self.sky.draw(&mut render_pass);
+        }
 
+        let cam_chunk = chunk_pos(cam_pos.floor_i32());
+        let render_distance = self.settings.render_distance as i32;
+        let mut visible: Vec<_> = (self.chunk_meshes.iter())
+            // past the render distance, chunks are completely hidden by fog anyway
+            .filter(|(pos, _)| {
+                let dist = (**pos - cam_chunk).abs();
+                dist.x <= render_distance && dist.y <= render_distance && dist.z <= render_distance
+            })
+            .collect();
+
+        render_pass.set_bind_group(0, &self.block_atlas_bind_group, &[]);
+        render_pass.set_bind_group(1, &self.bind_group1, &[]);
+        for layer in [RenderLayer::Opaque, RenderLayer::Cutout] {
+            render_pass.set_pipeline(&self.chunk_pipelines[layer as usize]);
+            for (_, mesh) in &visible {
+                mesh.draw(&mut render_pass, layer);
+            }
+        }
+
+        render_pass.set_pipeline(&self.render_pipeline);
         render_pass.set_bind_group(0, &self.bind_group0, &[]);
         render_pass.set_bind_group(1, &self.bind_group1, &[]);
 
         render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
         render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-        render_pass.draw_indexed(0..model::NUM_INDICES as u32, 0, 0..1);
+        render_pass.draw_indexed(0..model::NUM_INDICES, 0, 0..1);
+
+        render_pass.set_bind_group(0, &self.block_atlas_bind_group, &[]);
+        self.instances.draw(&mut render_pass);
+
+        // blended faces go last, so everything behind them has already been drawn.
+        // The faces in each chunk are already sorted, and the chunks are drawn furthest first
+        let chunk_dist = |pos: Vec3<i32>| {
+            let center = chunk_origin(pos).as_f32() + CHUNK_SIZE as f32 / 2.0;
+            (center - cam_pos).len_sq()
+        };
+        visible.sort_by(|(a, _), (b, _)| chunk_dist(**b).total_cmp(&chunk_dist(**a)));
+        render_pass.set_bind_group(0, &self.block_atlas_bind_group, &[]);
+        render_pass.set_bind_group(1, &self.bind_group1, &[]);
+        // Known limitation: the two blended layers of a chunk are sorted separately, and its
+        // translucent faces are all drawn before its water, whatever their depth. So ice behind
+        // water in the same chunk is blended over the water, instead of under it
+        for (_, mesh) in &visible {
+            for layer in [RenderLayer::Translucent, RenderLayer::Water] {
+                if mesh.layers[layer as usize].is_some() {
+                    render_pass.set_pipeline(&self.chunk_pipelines[layer as usize]);
+                    mesh.draw(&mut render_pass, layer);
+                }
+            }
+        }
         std::mem::drop(render_pass);
 
         self.queue.submit(std::iter::once(encoder.finish()));
@@ -354,29 +768,126 @@ impl State {
     }
 }
 
-pub async fn run() {
+/// Grabs the cursor, so that it stays in the window and is hidden.
+/// Prefers locking the cursor in place, but falls back to confining it to the window,
+/// since not every platform supports both.
+fn grab_cursor(window: &Window, grab: bool) {
+    let result = if grab {
+        window
+            .set_cursor_grab(CursorGrabMode::Locked)
+            .or_else(|_| window.set_cursor_grab(CursorGrabMode::Confined))
+    } else {
+        window.set_cursor_grab(CursorGrabMode::None)
+    };
+    if let Err(err) = result {
+        log::warn!("failed to set cursor grab: {err}");
+    }
+    window.set_cursor_visible(!grab);
+}
+
+#[derive(Default)]
+pub struct RunOptions {
+    pub seed: u64,
+    // if set, the input of every tick is recorded, and written to this file on exit
+    pub record: Option<PathBuf>,
+    pub settings: Settings,
+}
+
+/// How many seconds the animation time in `State::time_uniform` counts up to before it wraps
+/// back to 0. Every animation in the shaders has to repeat a whole number of times in this
+/// period, or it jumps when the time wraps.
+pub const ANIMATION_PERIOD: u64 = 3600;
+
+const TITLE: &str = "WGPU Voxel Game";
+/// The most ticks run in a single frame. After a stall (a slow frame, dragging the window, a
+/// breakpoint) the missed time beyond this is dropped, so the game slows down for a moment
+/// instead of spending every following frame catching up.
+const MAX_TICKS_PER_FRAME: u32 = 10;
+
+pub async fn run(options: RunOptions) {
     let event_loop = EventLoop::new();
     let window = WindowBuilder::new()
-        .with_title("WGPU Voxel Game")
+        .with_title(TITLE)
         .build(&event_loop)
         .unwrap();
-    window.set_cursor_visible(false);
 
-    let mut state = State::new(&window).await;
+    let mut state = State::new(&window, World::new(options.seed), options.settings).await;
     let mut frame_input = FrameInput::new();
+    let mut recording = options
+        .record
+        .map(|path| (path, Recording::new(options.seed)));
+
+    let mut last_frame = Instant::now();
+    let mut tick_lag = std::time::Duration::ZERO;
+
+    let mut cursor_grabbed = true;
+    grab_cursor(&window, cursor_grabbed);
+    let mut title = String::from(TITLE);
 
     event_loop.run(move |event, _, control_flow| match event {
+        // the camera should only follow the mouse while we own the cursor
+        Event::DeviceEvent {
+            event: DeviceEvent::MouseMotion { .. },
+            ..
+        } if !cursor_grabbed => {}
+
         e if frame_input.capture_event(&e) => {}
 
         Event::RedrawRequested(_) => {
-            state.update(&frame_input);
-            match state.render(&frame_input) {
+            // escape closes the console first, if it's open
+            let typing = state.world.console.is_open();
+            if cursor_grabbed && !typing && frame_input.key_just_pressed(Key::Escape) {
+                cursor_grabbed = false;
+                grab_cursor(&window, cursor_grabbed);
+            } else if !cursor_grabbed && frame_input.mouse_button_just_pressed(MouseButton::Left) {
+                cursor_grabbed = true;
+                grab_cursor(&window, cursor_grabbed);
+            }
+
+            let now = Instant::now();
+            tick_lag += now - last_frame;
+            last_frame = now;
+
+            tick_lag = tick_lag.min(TICK_DURATION * MAX_TICKS_PER_FRAME);
+
+            // the input of a frame is consumed by the first tick that runs after it,
+            // if no tick runs this frame, it carries over to the next one
+            while tick_lag >= TICK_DURATION {
+                tick_lag -= TICK_DURATION;
+                if let Some((_, recording)) = &mut recording {
+                    recording.record(&frame_input);
+                }
+                state.update(&frame_input);
+                frame_input.clear();
+            }
+
+            // there's no text rendering yet, so the command being typed is shown in the title
+            let new_title = match state.world.console.line() {
+                Some(line) => format!("{TITLE} - /{line}"),
+                None => String::from(TITLE),
+            };
+            if new_title != title {
+                title = new_title;
+                window.set_title(&title);
+            }
+
+            let tick_progress = tick_lag.as_secs_f32() / TICK_DURATION.as_secs_f32();
+            match state.render(&frame_input, tick_progress) {
                 Ok(_) => {}
                 Err(wgpu::SurfaceError::Lost) => state.resize(state.size),
                 Err(wgpu::SurfaceError::OutOfMemory) => *control_flow = ControlFlow::Exit,
                 Err(e) => eprintln!("{e:?}"),
             };
-            frame_input.clear();
+        }
+        Event::LoopDestroyed => {
+            if let Some((path, recording)) = &recording {
+                match recording.save(path) {
+                    Ok(()) => log::info!("saved recording to {}", path.display()),
+                    Err(err) => {
+                        log::error!("failed to save recording to {}: {err}", path.display())
+                    }
+                }
+            }
         }
         Event::MainEventsCleared => {
             window.request_redraw();
@@ -384,6 +895,13 @@ pub async fn run() {
         Event::WindowEvent { event, .. } => match event {
             WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
             WindowEvent::Resized(new_size) => state.resize(new_size),
+            WindowEvent::Focused(false) => {
+                // we won't receive release events for keys let go of while unfocused,
+                // so they would get "stuck" after alt-tabbing
+                frame_input.release_all();
+                cursor_grabbed = false;
+                grab_cursor(&window, cursor_grabbed);
+            }
             _ => {}
         },
         _ => {}
@@ -0,0 +1,365 @@
+//! Flood fill lighting.
+//!
+//! Every block stores two light levels, both in `0..=MAX_LIGHT`:
+//! - sky light, which falls straight down from the top of the world without losing any
+//!   brightness, then spreads out sideways and into caves, getting 1 darker per block.
+//! - block light, which spreads out from blocks with an `emission`, getting 1 darker per block.
+//!
+//! Light spreads through every block that isn't opaque.
+//! When a block changes, only the light around it is updated: light that came from (or through)
+//! it is removed, then the gap is filled back in from the light around it.
+
+use crate::chunk::{chunk_origin, CHUNK_SIZE};
+use crate::math::Vec3;
+use crate::model::Face;
+use crate::world::World;
+use std::collections::{HashSet, VecDeque};
+
+pub const MAX_LIGHT: u8 = 15;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LightKind {
+    Sky,
+    Block,
+}
+impl LightKind {
+    pub const ALL: [LightKind; 2] = [LightKind::Sky, LightKind::Block];
+
+    /// The light that spreads from a block with `level` light, to its neighbor on `face`.
+    #[inline(always)]
+    fn spread(self, level: u8, face: Face) -> u8 {
+        match (self, face) {
+            // sunlight shines straight down forever
+            (LightKind::Sky, Face::NegY) if level == MAX_LIGHT => MAX_LIGHT,
+            _ => level.saturating_sub(1),
+        }
+    }
+
+    /// The light a block emits on its own, that doesn't come from its neighbors.
+    #[inline(always)]
+    fn emission(self, world: &World, pos: Vec3<i32>) -> u8 {
+        match self {
+            LightKind::Sky => 0,
+            LightKind::Block => world.get_block(pos).info().emission,
+        }
+    }
+}
+
+/// Whether light can spread into the block at `pos`.
+#[inline(always)]
+fn transparent(world: &World, pos: Vec3<i32>) -> bool {
+    !world.get_block(pos).info().opaque
+}
+
+/// Lights the chunks at `chunk_positions` from scratch, as well as the light spreading out
+/// of them into their loaded neighbors. Any light already in them is ignored.
+pub fn light_chunks(world: &mut World, chunk_positions: &[Vec3<i32>]) {
+    let mut chunk_positions = chunk_positions.to_vec();
+    // the sky light of a column depends on the chunks above it, so those are lit first
+    chunk_positions.sort_by_key(|pos| -pos.y);
+    let in_set: HashSet<_> = chunk_positions.iter().copied().collect();
+
+    // this touches every block in the chunks, so it works on them directly instead of going
+    // through `World::set_light`, which has to look up the chunk every time
+    for &chunk_pos in &chunk_positions {
+        // falls into the chunk from the top, if nothing in the chunk above is in the way
+        let above = world.chunk(chunk_pos + Vec3::new(0, 1, 0));
+        let mut sky = [[MAX_LIGHT; CHUNK_SIZE as usize]; CHUNK_SIZE as usize];
+        if let Some(above) = above {
+            for z in 0..CHUNK_SIZE {
+                for x in 0..CHUNK_SIZE {
+                    let level = above.light(Vec3::new(x, 0, z), LightKind::Sky);
+                    sky[z as usize][x as usize] = level;
+                }
+            }
+        }
+
+        let chunk = world.chunks.get_mut(&chunk_pos).unwrap();
+        for z in 0..CHUNK_SIZE {
+            for x in 0..CHUNK_SIZE {
+                let sky = &mut sky[z as usize][x as usize];
+                for y in (0..CHUNK_SIZE).rev() {
+                    let local = Vec3::new(x, y, z);
+                    let info = chunk.get(local).info();
+                    if info.opaque || *sky != MAX_LIGHT {
+                        // only the unbroken light from the sky is filled in here, the rest
+                        // spreads in from the sides
+                        *sky = 0;
+                    }
+                    chunk.set_light(local, LightKind::Sky, *sky);
+                    chunk.set_light(local, LightKind::Block, info.emission);
+                }
+            }
+        }
+        world.dirty_chunks.insert(chunk_pos);
+    }
+
+    for kind in LightKind::ALL {
+        let mut queue = VecDeque::new();
+
+        for &chunk_pos in &chunk_positions {
+            let origin = chunk_origin(chunk_pos);
+            let chunk = world.chunk(chunk_pos).unwrap();
+            for y in 0..CHUNK_SIZE {
+                for z in 0..CHUNK_SIZE {
+                    for x in 0..CHUNK_SIZE {
+                        let local = Vec3::new(x, y, z);
+                        let level = chunk.light(local, kind);
+                        if level == 0 {
+                            continue;
+                        }
+                        // most lit blocks are surrounded by blocks that are already as bright as
+                        // they can make them, so spreading only starts at the edges of the light
+                        let at_edge = Face::ALL.into_iter().any(|face| {
+                            let spread = kind.spread(level, face);
+                            let neighbor = local + face.normal();
+                            let inside = (neighbor.x as u32) < CHUNK_SIZE as u32
+                                && (neighbor.y as u32) < CHUNK_SIZE as u32
+                                && (neighbor.z as u32) < CHUNK_SIZE as u32;
+                            if inside {
+                                chunk.light(neighbor, kind) < spread
+                                    && !chunk.get(neighbor).info().opaque
+                            } else {
+                                let neighbor = origin + neighbor;
+                                world.light(neighbor, kind).is_some_and(|n| n < spread)
+                                    && transparent(world, neighbor)
+                            }
+                        });
+                        if at_edge {
+                            queue.push_back(origin + local);
+                        }
+                    }
+                }
+            }
+        }
+        // light from neighboring chunks that were already lit spreads in from across the border
+        for &chunk_pos in &chunk_positions {
+            for face in Face::ALL {
+                if in_set.contains(&(chunk_pos + face.normal())) {
+                    continue;
+                }
+                let origin = chunk_origin(chunk_pos);
+                for a in 0..CHUNK_SIZE {
+                    for b in 0..CHUNK_SIZE {
+                        let pos = origin
+                            + match face {
+                                Face::PosX => Vec3::new(CHUNK_SIZE, a, b),
+                                Face::NegX => Vec3::new(-1, a, b),
+                                Face::PosY => Vec3::new(a, CHUNK_SIZE, b),
+                                Face::NegY => Vec3::new(a, -1, b),
+                                Face::PosZ => Vec3::new(a, b, CHUNK_SIZE),
+                                Face::NegZ => Vec3::new(a, b, -1),
+                            };
+                        if world.light(pos, kind).unwrap_or(0) > 0 {
+                            queue.push_back(pos);
+                        }
+                    }
+                }
+            }
+        }
+        spread_light(world, kind, queue);
+    }
+}
+
+/// Updates the light around `pos` after the block there changed.
+pub fn block_changed(world: &mut World, pos: Vec3<i32>) {
+    for kind in LightKind::ALL {
+        let mut queue = VecDeque::new();
+
+        if world.light(pos, kind).unwrap_or(0) > 0 {
+            remove_light(world, kind, pos, &mut queue);
+        }
+        let emission = kind.emission(world, pos);
+        if emission > 0 {
+            world.set_light(pos, kind, emission);
+            queue.push_back(pos);
+        }
+        // let the light around the block spread back into it
+        if transparent(world, pos) {
+            for face in Face::ALL {
+                let neighbor = pos + face.normal();
+                if world.light(neighbor, kind).unwrap_or(0) > 0 {
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+        spread_light(world, kind, queue);
+    }
+}
+
+/// Spreads light out from every position in `queue`, breadth first, so that every block is
+/// reached by the brightest path to it first.
+fn spread_light(world: &mut World, kind: LightKind, mut queue: VecDeque<Vec3<i32>>) {
+    while let Some(pos) = queue.pop_front() {
+        let Some(level) = world.light(pos, kind) else {
+            continue;
+        };
+        for face in Face::ALL {
+            let spread = kind.spread(level, face);
+            if spread == 0 {
+                continue;
+            }
+            let neighbor = pos + face.normal();
+            let Some(neighbor_level) = world.light(neighbor, kind) else {
+                continue;
+            };
+            if neighbor_level < spread && transparent(world, neighbor) {
+                world.set_light(neighbor, kind, spread);
+                queue.push_back(neighbor);
+            }
+        }
+    }
+}
+
+/// Darkens `pos`, and every block that was lit through it.
+/// Blocks at the edge of the darkened area that are lit from somewhere else are pushed to
+/// `refill`, to spread their light back into it.
+fn remove_light(
+    world: &mut World,
+    kind: LightKind,
+    pos: Vec3<i32>,
+    refill: &mut VecDeque<Vec3<i32>>,
+) {
+    let mut queue = VecDeque::new();
+    queue.push_back((pos, world.light(pos, kind).unwrap_or(0)));
+    world.set_light(pos, kind, 0);
+
+    while let Some((pos, level)) = queue.pop_front() {
+        for face in Face::ALL {
+            let neighbor = pos + face.normal();
+            let neighbor_level = match world.light(neighbor, kind) {
+                Some(0) | None => continue,
+                Some(level) => level,
+            };
+            // the neighbor could have gotten its light from this block
+            if kind.spread(level, face) >= neighbor_level {
+                world.set_light(neighbor, kind, 0);
+                queue.push_back((neighbor, neighbor_level));
+
+                let emission = kind.emission(world, neighbor);
+                if emission > 0 {
+                    world.set_light(neighbor, kind, emission);
+                    refill.push_back(neighbor);
+                }
+            } else {
+                refill.push_back(neighbor);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::Block;
+
+    /// A 3x3 chunk wide world with a floor of stone at y = 0, and air above.
+    fn flat_world() -> World {
+        let mut world = World::empty(0);
+        let mut chunks = vec![];
+        for x in -1..=1 {
+            for z in -1..=1 {
+                for y in 0..=1 {
+                    chunks.push(Vec3::new(x, y, z));
+                }
+            }
+        }
+        for &chunk_pos in &chunks {
+            world.chunks.insert(chunk_pos, Default::default());
+        }
+        for x in -CHUNK_SIZE..CHUNK_SIZE * 2 {
+            for z in -CHUNK_SIZE..CHUNK_SIZE * 2 {
+                let pos = Vec3::new(x, 0, z);
+                let chunk_pos = crate::chunk::chunk_pos(pos);
+                let local = crate::chunk::local_pos(pos);
+                world
+                    .chunks
+                    .get_mut(&chunk_pos)
+                    .unwrap()
+                    .set(local, Block::STONE);
+            }
+        }
+        light_chunks(&mut world, &chunks);
+        world
+    }
+
+    /// Checks that the light in the world is what it would be if it was lit from scratch.
+    fn assert_matches_full_relight(world: &World) {
+        let mut relit = world.clone();
+        let chunks: Vec<_> = relit.chunks.keys().copied().collect();
+        light_chunks(&mut relit, &chunks);
+        for (chunk_pos, chunk) in &world.chunks {
+            let relit_chunk = &relit.chunks[chunk_pos];
+            for y in 0..CHUNK_SIZE {
+                for z in 0..CHUNK_SIZE {
+                    for x in 0..CHUNK_SIZE {
+                        let local = Vec3::new(x, y, z);
+                        assert_eq!(
+                            chunk.packed_light(local),
+                            relit_chunk.packed_light(local),
+                            "at {:?}",
+                            chunk_origin(*chunk_pos) + local
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sky_light_falls_down_and_spreads() {
+        let mut world = flat_world();
+        assert_eq!(world.light(Vec3::new(0, 1, 0), LightKind::Sky), Some(15));
+        assert_eq!(world.light(Vec3::new(0, 0, 0), LightKind::Sky), Some(0));
+
+        // a roof casts a shadow, which gets darker further in
+        for x in -5..=5 {
+            for z in -5..=5 {
+                world.set_block(Vec3::new(x, 3, z), Block::STONE);
+            }
+        }
+        assert_eq!(world.light(Vec3::new(0, 1, 0), LightKind::Sky), Some(9));
+        assert_eq!(world.light(Vec3::new(5, 1, 0), LightKind::Sky), Some(14));
+        assert_eq!(world.light(Vec3::new(0, 4, 0), LightKind::Sky), Some(15));
+        assert_matches_full_relight(&world);
+
+        // and removing part of it lets the sky back in
+        world.set_block(Vec3::new(0, 3, 0), Block::AIR);
+        assert_eq!(world.light(Vec3::new(0, 1, 0), LightKind::Sky), Some(15));
+        assert_eq!(world.light(Vec3::new(1, 1, 0), LightKind::Sky), Some(14));
+        assert_matches_full_relight(&world);
+    }
+
+    #[test]
+    fn block_light_spreads_and_is_removed() {
+        let mut world = flat_world();
+        world.set_block(Vec3::new(0, 1, 0), Block::LAMP);
+        assert_eq!(world.light(Vec3::new(0, 1, 0), LightKind::Block), Some(15));
+        assert_eq!(world.light(Vec3::new(3, 1, 0), LightKind::Block), Some(12));
+        assert_eq!(world.light(Vec3::new(3, 1, 3), LightKind::Block), Some(9));
+        assert_matches_full_relight(&world);
+
+        // spreads across chunk borders
+        assert_eq!(world.light(Vec3::new(-1, 1, 0), LightKind::Block), Some(14));
+        assert_eq!(
+            world.light(Vec3::new(-1, 1, -1), LightKind::Block),
+            Some(13)
+        );
+
+        // a wall blocks the light, so it has to go around
+        for y in 1..=8 {
+            for z in -8..=8 {
+                world.set_block(Vec3::new(2, y, z), Block::STONE);
+            }
+        }
+        assert_eq!(world.light(Vec3::new(3, 1, 0), LightKind::Block), Some(0));
+        assert_eq!(world.light(Vec3::new(3, 9, 0), LightKind::Block), Some(4));
+        assert_matches_full_relight(&world);
+
+        // two lamps, removing one leaves the other's light
+        world.set_block(Vec3::new(-4, 1, 0), Block::LAMP);
+        world.set_block(Vec3::new(0, 1, 0), Block::AIR);
+        assert_eq!(world.light(Vec3::new(0, 1, 0), LightKind::Block), Some(11));
+        assert_matches_full_relight(&world);
+    }
+}
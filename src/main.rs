@@ -1,5 +1,50 @@
+use std::path::PathBuf;
+use wgpu_voxel_game::replay::Recording;
+use wgpu_voxel_game::RunOptions;
+
+const USAGE: &str = "usage: wgpu-voxel-game [--seed <seed>] [--record <file>] [--replay <file>] \
+                     [--no-reverse-z] [--flat-lighting] [--render-distance <chunks>]";
+
 fn main() {
     env_logger::init();
 
-    pollster::block_on(wgpu_voxel_game::run());
+    let mut options = RunOptions::default();
+    let mut replay = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| exit_with_usage());
+        match arg.as_str() {
+            "--seed" => options.seed = value().parse().unwrap_or_else(|_| exit_with_usage()),
+            "--record" => options.record = Some(PathBuf::from(value())),
+            "--replay" => replay = Some(PathBuf::from(value())),
+            "--no-reverse-z" => options.settings.reverse_z = false,
+            "--flat-lighting" => options.settings.smooth_lighting = false,
+            "--render-distance" => {
+                options.settings.render_distance =
+                    value().parse().unwrap_or_else(|_| exit_with_usage())
+            }
+            _ => exit_with_usage(),
+        }
+    }
+
+    // replays run headlessly, and report where the session ended up
+    if let Some(path) = replay {
+        let recording = Recording::load(&path).unwrap_or_else(|err| {
+            eprintln!("failed to load {}: {err}", path.display());
+            std::process::exit(1);
+        });
+        let world = recording.replay();
+        println!("replayed {} ticks", world.tick);
+        println!("player pos: {:?}", world.player.pos);
+        println!("player rot: {:?}", world.player.cam.rot);
+        return;
+    }
+
+    pollster::block_on(wgpu_voxel_game::run(options));
+}
+
+fn exit_with_usage() -> ! {
+    eprintln!("{USAGE}");
+    std::process::exit(2);
 }
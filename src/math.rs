@@ -1,6 +1,9 @@
 use std::fmt::Debug;
 use std::ops::{Add, Div, Mul, Sub};
 
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod simd;
+
 pub trait VecType: Clone + Copy {}
 impl<T: Clone + Copy> VecType for T {}
 
@@ -27,7 +30,8 @@ macro_rules! impl_op {
 
 macro_rules! define_vec {
 	($name:ident{$($field:ident),*};$size:literal) => {
-		#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+		// ordered by x, then y, then z, so integer vectors can key sorted collections
+		#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 		#[repr(C)]
 		pub struct $name<T: VecType> {
 			$(pub $field: T),*
@@ -52,8 +56,59 @@ macro_rules! define_vec {
 			pub fn map<E: VecType>(&self, f: impl Fn(T) -> E) -> $name<E> {
 				$name { $($field: f(self.$field)),* }
 			}
+
+			/// Component-wise minimum.
+			#[inline(always)]
+			pub fn min(self, rhs: Self) -> Self where T: PartialOrd {
+				Self { $($field: if rhs.$field < self.$field { rhs.$field } else { self.$field }),* }
+			}
+			/// Component-wise maximum.
+			#[inline(always)]
+			pub fn max(self, rhs: Self) -> Self where T: PartialOrd {
+				Self { $($field: if rhs.$field > self.$field { rhs.$field } else { self.$field }),* }
+			}
+		}
+		impl $name<f32> {
+			#[inline(always)]
+			pub fn abs(self) -> Self {
+				Self { $($field: self.$field.abs()),* }
+			}
+			#[inline(always)]
+			pub fn floor(self) -> Self {
+				Self { $($field: self.$field.floor()),* }
+			}
+			/// Linearly interpolates from `self` to `rhs` by `t` (0..1).
+			#[inline(always)]
+			pub fn lerp(self, rhs: Self, t: f32) -> Self {
+				Self { $($field: self.$field + (rhs.$field - self.$field) * t),* }
+			}
+
+			/// Rounds every component down, to the integer vector of the cell containing `self`.
+			#[inline(always)]
+			pub fn floor_i32(self) -> $name<i32> {
+				$name { $($field: self.$field.floor() as i32),* }
+			}
+			/// Truncates every component towards zero.
+			#[inline(always)]
+			pub fn as_i32(self) -> $name<i32> {
+				$name { $($field: self.$field as i32),* }
+			}
+		}
+		impl $name<i32> {
+			#[inline(always)]
+			pub fn as_f32(self) -> $name<f32> {
+				$name { $($field: self.$field as f32),* }
+			}
+			#[inline(always)]
+			pub fn abs(self) -> Self {
+				Self { $($field: self.$field.abs()),* }
+			}
 		}
+	}
+}
 
+macro_rules! impl_ops {
+	($name:ident{$($field:ident),*}) => {
 		impl_op!($name{$($field),*},Add,add,+);
 		impl_op!($name{$($field),*},Sub,sub,-);
 		impl_op!($name{$($field),*},Mul,mul,*);
@@ -61,10 +116,41 @@ macro_rules! define_vec {
 	}
 }
 
+/// Like `impl_op`, for `Vec4<f32>` only, so the `simd` feature can replace the scalar code.
+macro_rules! impl_vec4_op {
+    ($op_trait:ident,$op_fn:ident,$scalar_fn:ident,$simd_fn:ident) => {
+        impl $op_trait<Self> for Vec4<f32> {
+            type Output = Self;
+            #[inline(always)]
+            fn $op_fn(self, rhs: Self) -> Self {
+                #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+                return simd::$simd_fn(self, rhs);
+                #[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+                return self.$scalar_fn(rhs);
+            }
+        }
+        impl $op_trait<f32> for Vec4<f32> {
+            type Output = Self;
+            #[inline(always)]
+            fn $op_fn(self, rhs: f32) -> Self {
+                self.$op_fn(Self::all(rhs))
+            }
+        }
+    };
+}
+
 define_vec!(Vec2 { x, y };2);
 define_vec!(Vec3 { x, y, z };3);
 define_vec!(Vec4 { x, y, z, w };4);
 
+impl_ops!(Vec2 { x, y });
+impl_ops!(Vec3 { x, y, z });
+// only `f32` vectors are 4 wide, so `Vec4` arithmetic is `f32` only
+impl_vec4_op!(Add, add, add_scalar, vec4_add);
+impl_vec4_op!(Sub, sub, sub_scalar, vec4_sub);
+impl_vec4_op!(Mul, mul, mul_scalar, vec4_mul);
+impl_vec4_op!(Div, div, div_scalar, vec4_div);
+
 impl Vec2<f32> {
     #[inline(always)]
     pub fn len_sq(&self) -> f32 {
@@ -109,6 +195,15 @@ impl Vec3<f32> {
         self.x * rhs.x + self.y * rhs.y + self.z * rhs.z
     }
 
+    #[inline(always)]
+    pub fn cross(self, rhs: Self) -> Self {
+        Self {
+            x: self.y * rhs.z - self.z * rhs.y,
+            y: self.z * rhs.x - self.x * rhs.z,
+            z: self.x * rhs.y - self.y * rhs.x,
+        }
+    }
+
     #[inline(always)]
     pub fn norm(self) -> Self {
         let len = self.len();
@@ -131,7 +226,10 @@ impl Vec4<f32> {
 
     #[inline(always)]
     pub fn dot(self, rhs: Self) -> f32 {
-        self.x * rhs.x + self.y * rhs.y + self.z * rhs.z + self.w * rhs.w
+        #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+        return simd::vec4_dot(self, rhs);
+        #[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+        return self.dot_scalar(rhs);
     }
 
     #[inline(always)]
@@ -145,6 +243,53 @@ impl Vec4<f32> {
         }
     }
 }
+impl Vec4<f32> {
+    /// `self + rhs`, always computed without SIMD. Used to check, and benchmark, the `simd` feature.
+    #[inline(always)]
+    pub fn add_scalar(self, rhs: Self) -> Self {
+        Self::new(
+            self.x + rhs.x,
+            self.y + rhs.y,
+            self.z + rhs.z,
+            self.w + rhs.w,
+        )
+    }
+    /// `self - rhs`, always computed without SIMD.
+    #[inline(always)]
+    pub fn sub_scalar(self, rhs: Self) -> Self {
+        Self::new(
+            self.x - rhs.x,
+            self.y - rhs.y,
+            self.z - rhs.z,
+            self.w - rhs.w,
+        )
+    }
+    /// `self * rhs` (component-wise), always computed without SIMD.
+    #[inline(always)]
+    pub fn mul_scalar(self, rhs: Self) -> Self {
+        Self::new(
+            self.x * rhs.x,
+            self.y * rhs.y,
+            self.z * rhs.z,
+            self.w * rhs.w,
+        )
+    }
+    /// `self / rhs` (component-wise), always computed without SIMD.
+    #[inline(always)]
+    pub fn div_scalar(self, rhs: Self) -> Self {
+        Self::new(
+            self.x / rhs.x,
+            self.y / rhs.y,
+            self.z / rhs.z,
+            self.w / rhs.w,
+        )
+    }
+    /// `self.dot(rhs)`, always computed without SIMD.
+    #[inline(always)]
+    pub fn dot_scalar(self, rhs: Self) -> f32 {
+        self.x * rhs.x + self.y * rhs.y + self.z * rhs.z + self.w * rhs.w
+    }
+}
 
 // ---- FROM TUPLES & ARRAYS
 impl<T: Copy> From<(T, T)> for Vec2<T> {
@@ -210,7 +355,7 @@ pub struct Mat4(pub [[f32; 4]; 4]);
 impl Mat4 {
     #[inline(always)]
     pub fn pod(&self) -> [[f32; 4]; 4] {
-        self.0.clone()
+        self.0
     }
 
     #[inline(always)]
@@ -272,8 +417,8 @@ impl Mat4 {
         let (s, c) = (a.sin(), a.cos());
         let mut out = Self::identity();
         out.set(0, 0, c);
-        out.set(2, 0, s);
-        out.set(0, 2, -s);
+        out.set(2, 0, -s);
+        out.set(0, 2, s);
         out.set(2, 2, c);
         out
     }
@@ -284,13 +429,13 @@ impl Mat4 {
         let mut out = Self::identity();
         out.set(0, 0, c);
         out.set(1, 0, s);
-        out.set(0, 1, s);
+        out.set(0, 1, -s);
         out.set(1, 1, c);
         out
     }
 
     /// Creates a 4x4 matrix that rotates a `Vec3` by `rot`, translates by `trans`, and scales by `scale`.
-    /// Expects `fov` elements to be in radians.
+    /// Expects `rot` elements to be in radians.
     pub fn transformation(trans: Vec3<f32>, rot: Vec3<f32>, scale: Vec3<f32>) -> Self {
         Self::translation(trans)
             * Self::x_rotation(rot.x)
@@ -300,8 +445,26 @@ impl Mat4 {
     }
 
     /// Creates a 4x4 matrix that projects a `Vec3` in world space, to screen space.
-    /// expects `fov` to be in radians.
+    /// expects `fov` (the horizontal field of view) to be in radians.<p>
+    /// Depth is mapped to wgpu's 0..1 range, with `near` at 0 and `far` at 1.
     pub fn projection(fov: f32, aspect: f32, near: f32, far: f32) -> Self {
+        let x_scale = 1.0 / (fov / 2.0).tan();
+        let y_scale = x_scale * aspect;
+        let range = near - far;
+
+        let mut out = Self::empty();
+        out.set(0, 0, x_scale);
+        out.set(1, 1, y_scale);
+        out.set(2, 2, far / range);
+        out.set(3, 2, -1.0);
+        out.set(2, 3, (near * far) / range);
+        out
+    }
+
+    /// Like `projection`, but with depth reversed: `near` is mapped to 1, and `far` to 0.
+    /// Floats are most precise near 0, which reversing puts far away, where precision is needed most.
+    /// Use with `CompareFunction::Greater`, and clear depth to 0.
+    pub fn projection_reverse_z(fov: f32, aspect: f32, near: f32, far: f32) -> Self {
         let x_scale = 1.0 / (fov / 2.0).tan();
         let y_scale = x_scale * aspect;
         let range = far - near;
@@ -309,19 +472,90 @@ impl Mat4 {
         let mut out = Self::empty();
         out.set(0, 0, x_scale);
         out.set(1, 1, y_scale);
-        out.set(2, 2, -((far + near) / range));
+        out.set(2, 2, near / range);
         out.set(3, 2, -1.0);
-        out.set(2, 3, -((2.0 * near * far) / range));
+        out.set(2, 3, (near * far) / range);
         out
     }
 
-    /// Creates a 4x4 matrix that rotates a `Vec3` by `rot`, and translates it by the negative of `pos`.
-    /// Expects `rot` elements to be in radians.
-    pub fn view(pos: Vec3<f32>, rot: Vec3<f32>) -> Self {
-        Self::x_rotation(rot.x)
-            * Self::y_rotation(rot.y)
-            * Self::z_rotation(rot.z)
-            * Self::translation(pos * -1.0)
+    /// Like `projection`, but with the far plane at infinity, so nothing is ever clipped for being too far away.
+    pub fn projection_infinite(fov: f32, aspect: f32, near: f32) -> Self {
+        let mut out = Self::projection(fov, aspect, near, 1.0);
+        // the limit of `projection` as `far` goes to infinity
+        out.set(2, 2, -1.0);
+        out.set(2, 3, -near);
+        out
+    }
+
+    /// Like `projection_reverse_z`, but with the far plane at infinity.
+    /// Points infinitely far away are mapped to a depth of 0.
+    pub fn projection_infinite_reverse_z(fov: f32, aspect: f32, near: f32) -> Self {
+        let mut out = Self::projection_reverse_z(fov, aspect, near, 1.0);
+        // the limit of `projection_reverse_z` as `far` goes to infinity
+        out.set(2, 2, 0.0);
+        out.set(2, 3, near);
+        out
+    }
+
+    /// Creates a 4x4 matrix that rotates a `Vec3` by the inverse of `orientation`,
+    /// and translates it by the negative of `pos`.
+    /// This is the view matrix of a camera at `pos`, oriented by `orientation`.
+    pub fn view_quat(pos: Vec3<f32>, orientation: Quat) -> Self {
+        Self::rotation(orientation.conjugate()) * Self::translation(pos * -1.0)
+    }
+
+    /// Creates a 4x4 matrix that rotates a `Vec3` by `q`.
+    /// Expects `q` to be normalized.
+    pub fn rotation(q: Quat) -> Self {
+        let (x, y, z, w) = (q.x, q.y, q.z, q.w);
+        let mut out = Self::identity();
+        out.set(0, 0, 1.0 - 2.0 * (y * y + z * z));
+        out.set(0, 1, 2.0 * (x * y - w * z));
+        out.set(0, 2, 2.0 * (x * z + w * y));
+        out.set(1, 0, 2.0 * (x * y + w * z));
+        out.set(1, 1, 1.0 - 2.0 * (x * x + z * z));
+        out.set(1, 2, 2.0 * (y * z - w * x));
+        out.set(2, 0, 2.0 * (x * z - w * y));
+        out.set(2, 1, 2.0 * (y * z + w * x));
+        out.set(2, 2, 1.0 - 2.0 * (x * x + y * y));
+        out
+    }
+
+    /// Creates a 4x4 matrix for a camera at `eye`, looking at `target`.
+    /// `up` is the direction that should be up on screen, and must not be parallel to the view direction.
+    pub fn look_at(eye: Vec3<f32>, target: Vec3<f32>, up: Vec3<f32>) -> Self {
+        let f = (target - eye).norm();
+        let r = f.cross(up).norm();
+        let u = r.cross(f);
+
+        let mut out = Self::identity();
+        out.set(0, 0, r.x);
+        out.set(0, 1, r.y);
+        out.set(0, 2, r.z);
+        out.set(0, 3, -r.dot(eye));
+        out.set(1, 0, u.x);
+        out.set(1, 1, u.y);
+        out.set(1, 2, u.z);
+        out.set(1, 3, -u.dot(eye));
+        // the camera looks down the negative Z axis
+        out.set(2, 0, -f.x);
+        out.set(2, 1, -f.y);
+        out.set(2, 2, -f.z);
+        out.set(2, 3, f.dot(eye));
+        out
+    }
+
+    /// Creates a 4x4 matrix that projects the box `left..right`, `bottom..top`, `-near..-far`
+    /// to screen space, without perspective. Depth is mapped to wgpu's 0..1 range.
+    pub fn orthographic(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> Self {
+        let mut out = Self::identity();
+        out.set(0, 0, 2.0 / (right - left));
+        out.set(0, 3, -(right + left) / (right - left));
+        out.set(1, 1, 2.0 / (top - bottom));
+        out.set(1, 3, -(top + bottom) / (top - bottom));
+        out.set(2, 2, -1.0 / (far - near));
+        out.set(2, 3, -near / (far - near));
+        out
     }
 
     /// Creates a 4x4 matrix that translates a `Vec3` by `t`.
@@ -342,29 +576,404 @@ impl Mat4 {
         out
     }
 }
+impl Mat4 {
+    #[inline(always)]
+    pub fn transpose(&self) -> Self {
+        #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+        return simd::mat4_transpose(self);
+        #[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+        return self.transpose_scalar();
+    }
+
+    /// `self.transpose()`, always computed without SIMD.
+    pub fn transpose_scalar(&self) -> Self {
+        let mut out = Self::empty();
+        for i in 0..4 {
+            for j in 0..4 {
+                out.set(i, j, self.get(j, i));
+            }
+        }
+        out
+    }
+
+    pub fn determinant(&self) -> f32 {
+        let m = |i, j| self.get(i, j);
+        // 2x2 sub-determinants of the bottom two rows
+        let s0 = m(2, 0) * m(3, 1) - m(2, 1) * m(3, 0);
+        let s1 = m(2, 0) * m(3, 2) - m(2, 2) * m(3, 0);
+        let s2 = m(2, 0) * m(3, 3) - m(2, 3) * m(3, 0);
+        let s3 = m(2, 1) * m(3, 2) - m(2, 2) * m(3, 1);
+        let s4 = m(2, 1) * m(3, 3) - m(2, 3) * m(3, 1);
+        let s5 = m(2, 2) * m(3, 3) - m(2, 3) * m(3, 2);
+
+        m(0, 0) * (m(1, 1) * s5 - m(1, 2) * s4 + m(1, 3) * s3)
+            - m(0, 1) * (m(1, 0) * s5 - m(1, 2) * s2 + m(1, 3) * s1)
+            + m(0, 2) * (m(1, 0) * s4 - m(1, 1) * s2 + m(1, 3) * s0)
+            - m(0, 3) * (m(1, 0) * s3 - m(1, 1) * s1 + m(1, 2) * s0)
+    }
+
+    /// Returns the inverse of this matrix, or `None` if it is singular.
+    pub fn inverse(&self) -> Option<Self> {
+        let m = |i, j| self.get(i, j);
+        // 2x2 sub-determinants of the top two, and bottom two rows
+        let s0 = m(0, 0) * m(1, 1) - m(1, 0) * m(0, 1);
+        let s1 = m(0, 0) * m(1, 2) - m(1, 0) * m(0, 2);
+        let s2 = m(0, 0) * m(1, 3) - m(1, 0) * m(0, 3);
+        let s3 = m(0, 1) * m(1, 2) - m(1, 1) * m(0, 2);
+        let s4 = m(0, 1) * m(1, 3) - m(1, 1) * m(0, 3);
+        let s5 = m(0, 2) * m(1, 3) - m(1, 2) * m(0, 3);
+
+        let c5 = m(2, 2) * m(3, 3) - m(3, 2) * m(2, 3);
+        let c4 = m(2, 1) * m(3, 3) - m(3, 1) * m(2, 3);
+        let c3 = m(2, 1) * m(3, 2) - m(3, 1) * m(2, 2);
+        let c2 = m(2, 0) * m(3, 3) - m(3, 0) * m(2, 3);
+        let c1 = m(2, 0) * m(3, 2) - m(3, 0) * m(2, 2);
+        let c0 = m(2, 0) * m(3, 1) - m(3, 0) * m(2, 1);
+
+        let det = s0 * c5 - s1 * c4 + s2 * c3 + s3 * c2 - s4 * c1 + s5 * c0;
+        if det.abs() < f32::EPSILON * f32::EPSILON {
+            return None;
+        }
+        let inv_det = 1.0 / det;
+
+        let mut out = Self::empty();
+        out.set(0, 0, (m(1, 1) * c5 - m(1, 2) * c4 + m(1, 3) * c3) * inv_det);
+        out.set(
+            0,
+            1,
+            (-m(0, 1) * c5 + m(0, 2) * c4 - m(0, 3) * c3) * inv_det,
+        );
+        out.set(0, 2, (m(3, 1) * s5 - m(3, 2) * s4 + m(3, 3) * s3) * inv_det);
+        out.set(
+            0,
+            3,
+            (-m(2, 1) * s5 + m(2, 2) * s4 - m(2, 3) * s3) * inv_det,
+        );
+
+        out.set(
+            1,
+            0,
+            (-m(1, 0) * c5 + m(1, 2) * c2 - m(1, 3) * c1) * inv_det,
+        );
+        out.set(1, 1, (m(0, 0) * c5 - m(0, 2) * c2 + m(0, 3) * c1) * inv_det);
+        out.set(
+            1,
+            2,
+            (-m(3, 0) * s5 + m(3, 2) * s2 - m(3, 3) * s1) * inv_det,
+        );
+        out.set(1, 3, (m(2, 0) * s5 - m(2, 2) * s2 + m(2, 3) * s1) * inv_det);
+
+        out.set(2, 0, (m(1, 0) * c4 - m(1, 1) * c2 + m(1, 3) * c0) * inv_det);
+        out.set(
+            2,
+            1,
+            (-m(0, 0) * c4 + m(0, 1) * c2 - m(0, 3) * c0) * inv_det,
+        );
+        out.set(2, 2, (m(3, 0) * s4 - m(3, 1) * s2 + m(3, 3) * s0) * inv_det);
+        out.set(
+            2,
+            3,
+            (-m(2, 0) * s4 + m(2, 1) * s2 - m(2, 3) * s0) * inv_det,
+        );
+
+        out.set(
+            3,
+            0,
+            (-m(1, 0) * c3 + m(1, 1) * c1 - m(1, 2) * c0) * inv_det,
+        );
+        out.set(3, 1, (m(0, 0) * c3 - m(0, 1) * c1 + m(0, 2) * c0) * inv_det);
+        out.set(
+            3,
+            2,
+            (-m(3, 0) * s3 + m(3, 1) * s1 - m(3, 2) * s0) * inv_det,
+        );
+        out.set(3, 3, (m(2, 0) * s3 - m(2, 1) * s1 + m(2, 2) * s0) * inv_det);
+        Some(out)
+    }
+
+    /// Transforms the point `p`, including translation and the perspective divide.
+    pub fn transform_point(&self, p: Vec3<f32>) -> Vec3<f32> {
+        let v = *self * Vec4::new(p.x, p.y, p.z, 1.0);
+        Vec3::new(v.x / v.w, v.y / v.w, v.z / v.w)
+    }
+
+    /// Transforms the direction `d`. Translation doesn't affect directions.
+    pub fn transform_dir(&self, d: Vec3<f32>) -> Vec3<f32> {
+        let v = *self * Vec4::new(d.x, d.y, d.z, 0.0);
+        Vec3::new(v.x, v.y, v.z)
+    }
+
+    /// The upper-left 3x3 of this matrix, without the translation.
+    pub fn mat3(&self) -> Mat3 {
+        let mut out = Mat3::empty();
+        for i in 0..3 {
+            for j in 0..3 {
+                out.set(i, j, self.get(i, j));
+            }
+        }
+        out
+    }
+
+    /// Creates the matrix that transforms normals for a model transformed by this matrix.
+    /// Normals must be transformed by the inverse-transpose, to stay perpendicular
+    /// to their surface under non-uniform scaling.
+    pub fn normal_matrix(&self) -> Option<Mat3> {
+        Some(self.mat3().inverse()?.transpose())
+    }
+}
+impl Mat4 {
+    /// `self * v`, always computed without SIMD. Used to check, and benchmark, the `simd` feature.
+    pub fn mul_vec4_scalar(&self, v: Vec4<f32>) -> Vec4<f32> {
+        Vec4 {
+            x: self.get_col(0).dot_scalar(v),
+            y: self.get_col(1).dot_scalar(v),
+            z: self.get_col(2).dot_scalar(v),
+            w: self.get_col(3).dot_scalar(v),
+        }
+    }
+
+    /// `self * other`, always computed without SIMD. Used to check, and benchmark, the `simd` feature.
+    pub fn mul_scalar(&self, other: &Self) -> Self {
+        let mut out = Self::empty();
+        out.set(0, 0, self.get_col(0).dot_scalar(other.get_row(0)));
+        out.set(1, 0, self.get_col(1).dot_scalar(other.get_row(0)));
+        out.set(2, 0, self.get_col(2).dot_scalar(other.get_row(0)));
+        out.set(3, 0, self.get_col(3).dot_scalar(other.get_row(0)));
+
+        out.set(0, 1, self.get_col(0).dot_scalar(other.get_row(1)));
+        out.set(1, 1, self.get_col(1).dot_scalar(other.get_row(1)));
+        out.set(2, 1, self.get_col(2).dot_scalar(other.get_row(1)));
+        out.set(3, 1, self.get_col(3).dot_scalar(other.get_row(1)));
+
+        out.set(0, 2, self.get_col(0).dot_scalar(other.get_row(2)));
+        out.set(1, 2, self.get_col(1).dot_scalar(other.get_row(2)));
+        out.set(2, 2, self.get_col(2).dot_scalar(other.get_row(2)));
+        out.set(3, 2, self.get_col(3).dot_scalar(other.get_row(2)));
+
+        out.set(0, 3, self.get_col(0).dot_scalar(other.get_row(3)));
+        out.set(1, 3, self.get_col(1).dot_scalar(other.get_row(3)));
+        out.set(2, 3, self.get_col(2).dot_scalar(other.get_row(3)));
+        out.set(3, 3, self.get_col(3).dot_scalar(other.get_row(3)));
+        out
+    }
+}
+impl std::ops::Mul<Vec4<f32>> for Mat4 {
+    type Output = Vec4<f32>;
+    #[inline(always)]
+    fn mul(self, v: Vec4<f32>) -> Self::Output {
+        #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+        return simd::mat4_mul_vec4(&self, v);
+        #[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+        return self.mul_vec4_scalar(v);
+    }
+}
 impl std::ops::Mul for Mat4 {
     type Output = Self;
+    #[inline(always)]
     fn mul(self, other: Self) -> Self::Output {
+        #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+        return simd::mat4_mul(&self, &other);
+        #[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+        return self.mul_scalar(&other);
+    }
+}
+impl From<Quat> for Mat4 {
+    fn from(q: Quat) -> Self {
+        Self::rotation(q)
+    }
+}
+
+// ---- MATRIX 3x3 ----
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Mat3(pub [[f32; 3]; 3]);
+impl Mat3 {
+    #[inline(always)]
+    pub const fn empty() -> Self {
+        Self([[0.0; 3]; 3])
+    }
+    #[inline(always)]
+    pub const fn identity() -> Self {
+        Self([[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]])
+    }
+
+    #[inline(always)] // column-major
+    pub const fn get(&self, col: usize, row: usize) -> f32 {
+        self.0[row][col]
+    }
+    #[inline(always)] // column-major
+    pub fn set(&mut self, col: usize, row: usize, value: f32) {
+        self.0[row][col] = value;
+    }
+
+    pub fn transpose(&self) -> Self {
         let mut out = Self::empty();
-        out.set(0, 0, self.get_col(0).dot(other.get_row(0)) as f32);
-        out.set(1, 0, self.get_col(1).dot(other.get_row(0)) as f32);
-        out.set(2, 0, self.get_col(2).dot(other.get_row(0)) as f32);
-        out.set(3, 0, self.get_col(3).dot(other.get_row(0)) as f32);
-
-        out.set(0, 1, self.get_col(0).dot(other.get_row(1)) as f32);
-        out.set(1, 1, self.get_col(1).dot(other.get_row(1)) as f32);
-        out.set(2, 1, self.get_col(2).dot(other.get_row(1)) as f32);
-        out.set(3, 1, self.get_col(3).dot(other.get_row(1)) as f32);
-
-        out.set(0, 2, self.get_col(0).dot(other.get_row(2)) as f32);
-        out.set(1, 2, self.get_col(1).dot(other.get_row(2)) as f32);
-        out.set(2, 2, self.get_col(2).dot(other.get_row(2)) as f32);
-        out.set(3, 2, self.get_col(3).dot(other.get_row(2)) as f32);
-
-        out.set(0, 3, self.get_col(0).dot(other.get_row(3)) as f32);
-        out.set(1, 3, self.get_col(1).dot(other.get_row(3)) as f32);
-        out.set(2, 3, self.get_col(2).dot(other.get_row(3)) as f32);
-        out.set(3, 3, self.get_col(3).dot(other.get_row(3)) as f32);
+        for i in 0..3 {
+            for j in 0..3 {
+                out.set(i, j, self.get(j, i));
+            }
+        }
+        out
+    }
+
+    pub fn determinant(&self) -> f32 {
+        let m = |i, j| self.get(i, j);
+        m(0, 0) * (m(1, 1) * m(2, 2) - m(1, 2) * m(2, 1))
+            - m(0, 1) * (m(1, 0) * m(2, 2) - m(1, 2) * m(2, 0))
+            + m(0, 2) * (m(1, 0) * m(2, 1) - m(1, 1) * m(2, 0))
+    }
+
+    /// Returns the inverse of this matrix, or `None` if it is singular.
+    pub fn inverse(&self) -> Option<Self> {
+        let det = self.determinant();
+        if det.abs() < f32::EPSILON * f32::EPSILON {
+            return None;
+        }
+        let inv_det = 1.0 / det;
+        let m = |i, j| self.get(i, j);
+
+        let mut out = Self::empty();
+        out.set(0, 0, (m(1, 1) * m(2, 2) - m(1, 2) * m(2, 1)) * inv_det);
+        out.set(0, 1, (m(0, 2) * m(2, 1) - m(0, 1) * m(2, 2)) * inv_det);
+        out.set(0, 2, (m(0, 1) * m(1, 2) - m(0, 2) * m(1, 1)) * inv_det);
+        out.set(1, 0, (m(1, 2) * m(2, 0) - m(1, 0) * m(2, 2)) * inv_det);
+        out.set(1, 1, (m(0, 0) * m(2, 2) - m(0, 2) * m(2, 0)) * inv_det);
+        out.set(1, 2, (m(0, 2) * m(1, 0) - m(0, 0) * m(1, 2)) * inv_det);
+        out.set(2, 0, (m(1, 0) * m(2, 1) - m(1, 1) * m(2, 0)) * inv_det);
+        out.set(2, 1, (m(0, 1) * m(2, 0) - m(0, 0) * m(2, 1)) * inv_det);
+        out.set(2, 2, (m(0, 0) * m(1, 1) - m(0, 1) * m(1, 0)) * inv_det);
+        Some(out)
+    }
+}
+impl std::ops::Mul<Vec3<f32>> for Mat3 {
+    type Output = Vec3<f32>;
+    fn mul(self, v: Vec3<f32>) -> Self::Output {
+        let row = |i| Vec3::new(self.get(i, 0), self.get(i, 1), self.get(i, 2));
+        Vec3::new(row(0).dot(v), row(1).dot(v), row(2).dot(v))
+    }
+}
+impl std::ops::Mul for Mat3 {
+    type Output = Self;
+    fn mul(self, other: Self) -> Self::Output {
+        let mut out = Self::empty();
+        for i in 0..3 {
+            for j in 0..3 {
+                let sum = (0..3).map(|k| self.get(i, k) * other.get(k, j)).sum();
+                out.set(i, j, sum);
+            }
+        }
         out
     }
 }
+
+// ---- QUATERNION ----
+/// A rotation, stored as a unit quaternion.
+/// Rotations are right-handed: a positive angle about an axis pointing at the viewer is counter-clockwise.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Quat {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+impl Quat {
+    #[inline(always)]
+    pub const fn new(x: f32, y: f32, z: f32, w: f32) -> Self {
+        Self { x, y, z, w }
+    }
+    #[inline(always)]
+    pub const fn identity() -> Self {
+        Self::new(0.0, 0.0, 0.0, 1.0)
+    }
+
+    /// Creates a rotation of `angle` about `axis`.
+    /// Expects `axis` to be normalized, and `angle` to be in radians.
+    pub fn from_axis_angle(axis: Vec3<f32>, angle: f32) -> Self {
+        let (s, c) = (angle * 0.5).sin_cos();
+        Self::new(axis.x * s, axis.y * s, axis.z * s, c)
+    }
+
+    /// Creates a rotation from rotations about the X, Y, and Z axes.
+    /// Expects `rot` elements to be in radians.<p>
+    /// The rotations are applied in Z, X, Y order (roll, then pitch, then yaw),
+    /// which is what a camera wants: yaw never tilts the horizon.
+    pub fn from_euler(rot: Vec3<f32>) -> Self {
+        let x = Self::from_axis_angle(Vec3::new(1.0, 0.0, 0.0), rot.x);
+        let y = Self::from_axis_angle(Vec3::new(0.0, 1.0, 0.0), rot.y);
+        let z = Self::from_axis_angle(Vec3::new(0.0, 0.0, 1.0), rot.z);
+        y * x * z
+    }
+
+    #[inline(always)]
+    pub fn dot(self, rhs: Self) -> f32 {
+        self.x * rhs.x + self.y * rhs.y + self.z * rhs.z + self.w * rhs.w
+    }
+    #[inline(always)]
+    pub fn len(&self) -> f32 {
+        self.dot(*self).sqrt()
+    }
+    #[inline(always)]
+    pub fn norm(self) -> Self {
+        let len = self.len();
+        Self::new(self.x / len, self.y / len, self.z / len, self.w / len)
+    }
+
+    /// The inverse rotation, for a normalized quaternion.
+    #[inline(always)]
+    pub fn conjugate(self) -> Self {
+        Self::new(-self.x, -self.y, -self.z, self.w)
+    }
+
+    /// Rotates `v` by this rotation.
+    pub fn rotate(self, v: Vec3<f32>) -> Vec3<f32> {
+        // v' = v + 2w(q x v) + 2(q x (q x v)), where q is the vector part
+        let q = Vec3::new(self.x, self.y, self.z);
+        let t = q.cross(v) * 2.0;
+        v + t * self.w + q.cross(t)
+    }
+
+    /// Spherically interpolates from `a` to `b` by `t` (0..1), along the shortest path.
+    pub fn slerp(a: Self, b: Self, t: f32) -> Self {
+        let mut b = b;
+        let mut cos = a.dot(b);
+        // q and -q are the same rotation, but interpolating towards the one
+        // on the other side of the hypersphere would take the long way around
+        if cos < 0.0 {
+            b = Self::new(-b.x, -b.y, -b.z, -b.w);
+            cos = -cos;
+        }
+
+        let (wa, wb) = if cos > 0.9995 {
+            // the rotations are nearly identical, so sin(angle) is nearly 0.
+            // a normalized linear interpolation is accurate enough here
+            (1.0 - t, t)
+        } else {
+            let angle = cos.acos();
+            let sin = angle.sin();
+            (((1.0 - t) * angle).sin() / sin, (t * angle).sin() / sin)
+        };
+        Self::new(
+            a.x * wa + b.x * wb,
+            a.y * wa + b.y * wb,
+            a.z * wa + b.z * wb,
+            a.w * wa + b.w * wb,
+        )
+        .norm()
+    }
+}
+impl std::ops::Mul for Quat {
+    type Output = Self;
+    /// Combines two rotations. `a * b` rotates by `b`, then by `a`.
+    fn mul(self, rhs: Self) -> Self::Output {
+        Self::new(
+            self.w * rhs.x + self.x * rhs.w + self.y * rhs.z - self.z * rhs.y,
+            self.w * rhs.y - self.x * rhs.z + self.y * rhs.w + self.z * rhs.x,
+            self.w * rhs.z + self.x * rhs.y - self.y * rhs.x + self.z * rhs.w,
+            self.w * rhs.w - self.x * rhs.x - self.y * rhs.y - self.z * rhs.z,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests;
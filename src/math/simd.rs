@@ -0,0 +1,138 @@
+//! SSE implementations of the hot `Mat4` and `Vec4<f32>` operations, enabled by the `simd` feature.
+//! SSE is part of the x86_64 baseline, so no runtime feature detection is needed.
+//!
+//! `Mat4` stores its columns contiguously (`self.0[col]`), so each column is one `__m128`,
+//! and every operation here is a sum of columns scaled by broadcast scalars.
+
+use super::{Mat4, Vec4};
+use std::arch::x86_64::*;
+
+#[inline(always)]
+fn load_cols(m: &Mat4) -> [__m128; 4] {
+    // SAFETY: each column is 4 contiguous f32s, and `_mm_loadu_ps` has no alignment requirement
+    unsafe {
+        [
+            _mm_loadu_ps(m.0[0].as_ptr()),
+            _mm_loadu_ps(m.0[1].as_ptr()),
+            _mm_loadu_ps(m.0[2].as_ptr()),
+            _mm_loadu_ps(m.0[3].as_ptr()),
+        ]
+    }
+}
+
+#[inline(always)]
+fn store_cols(cols: [__m128; 4]) -> Mat4 {
+    let mut out = Mat4::empty();
+    // SAFETY: each column is 4 contiguous f32s, and `_mm_storeu_ps` has no alignment requirement
+    unsafe {
+        _mm_storeu_ps(out.0[0].as_mut_ptr(), cols[0]);
+        _mm_storeu_ps(out.0[1].as_mut_ptr(), cols[1]);
+        _mm_storeu_ps(out.0[2].as_mut_ptr(), cols[2]);
+        _mm_storeu_ps(out.0[3].as_mut_ptr(), cols[3]);
+    }
+    out
+}
+
+/// `cols[0] * v[0] + cols[1] * v[1] + cols[2] * v[2] + cols[3] * v[3]`
+#[inline(always)]
+fn linear_combination(cols: &[__m128; 4], v: [f32; 4]) -> __m128 {
+    // SAFETY: SSE is always available on x86_64
+    unsafe {
+        let x = _mm_mul_ps(cols[0], _mm_set1_ps(v[0]));
+        let y = _mm_mul_ps(cols[1], _mm_set1_ps(v[1]));
+        let z = _mm_mul_ps(cols[2], _mm_set1_ps(v[2]));
+        let w = _mm_mul_ps(cols[3], _mm_set1_ps(v[3]));
+        _mm_add_ps(_mm_add_ps(x, y), _mm_add_ps(z, w))
+    }
+}
+
+#[inline(always)]
+pub fn mat4_mul(a: &Mat4, b: &Mat4) -> Mat4 {
+    let a = load_cols(a);
+    // each column of the product is `a` applied to the matching column of `b`
+    store_cols([
+        linear_combination(&a, b.0[0]),
+        linear_combination(&a, b.0[1]),
+        linear_combination(&a, b.0[2]),
+        linear_combination(&a, b.0[3]),
+    ])
+}
+
+#[inline(always)]
+pub fn mat4_mul_vec4(m: &Mat4, v: Vec4<f32>) -> Vec4<f32> {
+    let cols = load_cols(m);
+    let mut out = [0.0; 4];
+    // SAFETY: `out` is 4 contiguous f32s
+    unsafe { _mm_storeu_ps(out.as_mut_ptr(), linear_combination(&cols, v.pod())) };
+    Vec4::from(out)
+}
+
+#[inline(always)]
+pub fn mat4_transpose(m: &Mat4) -> Mat4 {
+    let [c0, c1, c2, c3] = load_cols(m);
+    // SAFETY: SSE is always available on x86_64
+    unsafe {
+        // the same shuffles as `_MM_TRANSPOSE4_PS`
+        let t0 = _mm_unpacklo_ps(c0, c1);
+        let t1 = _mm_unpacklo_ps(c2, c3);
+        let t2 = _mm_unpackhi_ps(c0, c1);
+        let t3 = _mm_unpackhi_ps(c2, c3);
+        store_cols([
+            _mm_movelh_ps(t0, t1),
+            _mm_movehl_ps(t1, t0),
+            _mm_movelh_ps(t2, t3),
+            _mm_movehl_ps(t3, t2),
+        ])
+    }
+}
+
+#[inline(always)]
+fn load_vec4(v: Vec4<f32>) -> __m128 {
+    let v = v.pod();
+    // SAFETY: `v` is 4 contiguous f32s
+    unsafe { _mm_loadu_ps(v.as_ptr()) }
+}
+
+#[inline(always)]
+fn store_vec4(v: __m128) -> Vec4<f32> {
+    let mut out = [0.0; 4];
+    // SAFETY: `out` is 4 contiguous f32s
+    unsafe { _mm_storeu_ps(out.as_mut_ptr(), v) };
+    Vec4::from(out)
+}
+
+#[inline(always)]
+pub fn vec4_add(a: Vec4<f32>, b: Vec4<f32>) -> Vec4<f32> {
+    // SAFETY: SSE is always available on x86_64
+    store_vec4(unsafe { _mm_add_ps(load_vec4(a), load_vec4(b)) })
+}
+
+#[inline(always)]
+pub fn vec4_sub(a: Vec4<f32>, b: Vec4<f32>) -> Vec4<f32> {
+    // SAFETY: SSE is always available on x86_64
+    store_vec4(unsafe { _mm_sub_ps(load_vec4(a), load_vec4(b)) })
+}
+
+#[inline(always)]
+pub fn vec4_mul(a: Vec4<f32>, b: Vec4<f32>) -> Vec4<f32> {
+    // SAFETY: SSE is always available on x86_64
+    store_vec4(unsafe { _mm_mul_ps(load_vec4(a), load_vec4(b)) })
+}
+
+#[inline(always)]
+pub fn vec4_div(a: Vec4<f32>, b: Vec4<f32>) -> Vec4<f32> {
+    // SAFETY: SSE is always available on x86_64
+    store_vec4(unsafe { _mm_div_ps(load_vec4(a), load_vec4(b)) })
+}
+
+#[inline(always)]
+pub fn vec4_dot(a: Vec4<f32>, b: Vec4<f32>) -> f32 {
+    // SAFETY: SSE is always available on x86_64
+    unsafe {
+        let products = _mm_mul_ps(load_vec4(a), load_vec4(b));
+        // (x + z, y + w, ..), then the sum of those two
+        let pairs = _mm_add_ps(products, _mm_movehl_ps(products, products));
+        let sum = _mm_add_ss(pairs, _mm_shuffle_ps::<0b01>(pairs, pairs));
+        _mm_cvtss_f32(sum)
+    }
+}
@@ -0,0 +1,697 @@
+use super::*;
+
+const EPSILON: f32 = 1e-5;
+
+fn assert_vec3_eq(a: Vec3<f32>, b: Vec3<f32>) {
+    assert!((a - b).len() < EPSILON, "{a:?} != {b:?}");
+}
+
+fn assert_mat4_eq(a: Mat4, b: Mat4) {
+    for i in 0..4 {
+        for j in 0..4 {
+            assert!(
+                (a.get(i, j) - b.get(i, j)).abs() < EPSILON,
+                "element ({i}, {j}) differs:\n{a:?}\n{b:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn vec3_cross() {
+    let x = Vec3::new(1.0, 0.0, 0.0);
+    let y = Vec3::new(0.0, 1.0, 0.0);
+    let z = Vec3::new(0.0, 0.0, 1.0);
+    assert_vec3_eq(x.cross(y), z);
+    assert_vec3_eq(y.cross(z), x);
+    assert_vec3_eq(z.cross(x), y);
+    assert_vec3_eq(y.cross(x), z * -1.0);
+    assert_vec3_eq(
+        Vec3::new(1.0, 2.0, 3.0).cross(Vec3::new(4.0, 5.0, 6.0)),
+        Vec3::new(-3.0, 6.0, -3.0),
+    );
+}
+
+#[test]
+fn vec_component_wise() {
+    let a = Vec3::new(-1.5, 2.0, 0.25);
+    let b = Vec3::new(1.0, -3.0, 0.5);
+    assert_eq!(a.min(b), Vec3::new(-1.5, -3.0, 0.25));
+    assert_eq!(a.max(b), Vec3::new(1.0, 2.0, 0.5));
+    assert_eq!(a.abs(), Vec3::new(1.5, 2.0, 0.25));
+    assert_eq!(a.floor(), Vec3::new(-2.0, 2.0, 0.0));
+    assert_eq!(a.lerp(b, 0.5), Vec3::new(-0.25, -0.5, 0.375));
+    assert_eq!(Vec2::new(1, 5).min(Vec2::new(3, 2)), Vec2::new(1, 2));
+}
+
+#[test]
+fn vec_integer_conversions() {
+    let v = Vec3::new(-1.5, 2.7, -0.2);
+    assert_eq!(v.floor_i32(), Vec3::new(-2, 2, -1));
+    assert_eq!(v.as_i32(), Vec3::new(-1, 2, 0));
+    assert_eq!(Vec3::new(-2, 2, -1).as_f32(), Vec3::new(-2.0, 2.0, -1.0));
+}
+
+#[test]
+fn mat4_transpose() {
+    let mut m = Mat4::empty();
+    for i in 0..4 {
+        for j in 0..4 {
+            m.set(i, j, (i * 4 + j) as f32);
+        }
+    }
+    let t = m.transpose();
+    for i in 0..4 {
+        for j in 0..4 {
+            assert_eq!(t.get(i, j), m.get(j, i));
+        }
+    }
+}
+
+#[test]
+fn mat4_determinant() {
+    assert_eq!(Mat4::identity().determinant(), 1.0);
+    assert_eq!(Mat4::scaling(Vec3::new(2.0, 3.0, 4.0)).determinant(), 24.0);
+    assert_eq!(
+        Mat4::translation(Vec3::new(5.0, 6.0, 7.0)).determinant(),
+        1.0
+    );
+}
+
+#[test]
+fn mat4_inverse_known_values() {
+    let t = Mat4::translation(Vec3::new(1.0, 2.0, 3.0));
+    assert_mat4_eq(
+        t.inverse().unwrap(),
+        Mat4::translation(Vec3::new(-1.0, -2.0, -3.0)),
+    );
+
+    let s = Mat4::scaling(Vec3::new(2.0, 4.0, 0.5));
+    assert_mat4_eq(
+        s.inverse().unwrap(),
+        Mat4::scaling(Vec3::new(0.5, 0.25, 2.0)),
+    );
+
+    // a general matrix, with an inverse computed by hand
+    let m = Mat4([
+        [2.0, 0.0, 0.0, 0.0],
+        [1.0, 1.0, 0.0, 0.0],
+        [0.0, 0.0, 4.0, 0.0],
+        [0.0, 3.0, 0.0, 1.0],
+    ]);
+    let inv = Mat4([
+        [0.5, 0.0, 0.0, 0.0],
+        [-0.5, 1.0, 0.0, 0.0],
+        [0.0, 0.0, 0.25, 0.0],
+        [1.5, -3.0, 0.0, 1.0],
+    ]);
+    assert_mat4_eq(m.inverse().unwrap(), inv);
+
+    assert!(Mat4::empty().inverse().is_none());
+    assert!(Mat4::scaling(Vec3::new(1.0, 0.0, 1.0)).inverse().is_none());
+}
+
+#[test]
+fn mat4_mul_vec4() {
+    let m = Mat4::translation(Vec3::new(1.0, 2.0, 3.0));
+    assert_eq!(
+        m * Vec4::new(1.0, 1.0, 1.0, 1.0),
+        Vec4::new(2.0, 3.0, 4.0, 1.0)
+    );
+    assert_eq!(
+        m * Vec4::new(1.0, 1.0, 1.0, 0.0),
+        Vec4::new(1.0, 1.0, 1.0, 0.0)
+    );
+
+    let s = Mat4::scaling(Vec3::new(2.0, 3.0, 4.0));
+    assert_eq!(
+        s * Vec4::new(1.0, 1.0, 1.0, 1.0),
+        Vec4::new(2.0, 3.0, 4.0, 1.0)
+    );
+}
+
+#[test]
+fn mat4_transform_point_and_dir() {
+    let m = Mat4::translation(Vec3::new(1.0, 2.0, 3.0)) * Mat4::scaling(Vec3::all(2.0));
+    assert_vec3_eq(
+        m.transform_point(Vec3::new(1.0, 0.0, 0.0)),
+        Vec3::new(3.0, 2.0, 3.0),
+    );
+    assert_vec3_eq(
+        m.transform_dir(Vec3::new(1.0, 0.0, 0.0)),
+        Vec3::new(2.0, 0.0, 0.0),
+    );
+
+    // the perspective divide is applied to points
+    let mut w = Mat4::identity();
+    w.set(3, 3, 2.0);
+    assert_vec3_eq(
+        w.transform_point(Vec3::new(2.0, 4.0, 6.0)),
+        Vec3::new(1.0, 2.0, 3.0),
+    );
+}
+
+#[test]
+fn mat4_look_at() {
+    let eye = Vec3::new(1.0, 2.0, 3.0);
+    let up = Vec3::new(0.0, 1.0, 0.0);
+
+    // looking down -Z is just a translation
+    let m = Mat4::look_at(eye, eye + Vec3::new(0.0, 0.0, -1.0), up);
+    assert_mat4_eq(m, Mat4::translation(eye * -1.0));
+
+    // the eye ends up at the origin, and the target straight ahead along -Z
+    let target = Vec3::new(4.0, -2.0, 0.0);
+    let m = Mat4::look_at(eye, target, up);
+    assert_vec3_eq(m.transform_point(eye), Vec3::all(0.0));
+    let dist = (target - eye).len();
+    assert_vec3_eq(m.transform_point(target), Vec3::new(0.0, 0.0, -dist));
+
+    // looking down +X, so +Z is to the right
+    let m = Mat4::look_at(Vec3::all(0.0), Vec3::new(1.0, 0.0, 0.0), up);
+    assert_vec3_eq(
+        m.transform_dir(Vec3::new(0.0, 0.0, 1.0)),
+        Vec3::new(1.0, 0.0, 0.0),
+    );
+}
+
+#[test]
+fn mat4_orthographic() {
+    let m = Mat4::orthographic(-2.0, 4.0, -1.0, 3.0, 1.0, 11.0);
+    assert_vec3_eq(
+        m.transform_point(Vec3::new(-2.0, -1.0, -1.0)),
+        Vec3::new(-1.0, -1.0, 0.0),
+    );
+    assert_vec3_eq(
+        m.transform_point(Vec3::new(4.0, 3.0, -11.0)),
+        Vec3::new(1.0, 1.0, 1.0),
+    );
+    assert_vec3_eq(
+        m.transform_point(Vec3::new(1.0, 1.0, -6.0)),
+        Vec3::new(0.0, 0.0, 0.5),
+    );
+}
+
+#[test]
+fn mat3_inverse_and_normal_matrix() {
+    let m = Mat4::scaling(Vec3::new(2.0, 1.0, 1.0));
+    let normal = m.normal_matrix().unwrap();
+    // a surface along the diagonal of the XY plane gets stretched along X,
+    // so its normal must tilt towards Y to stay perpendicular
+    let n = (normal * Vec3::new(1.0, 1.0, 0.0)).norm();
+    let tangent = m.transform_dir(Vec3::new(1.0, -1.0, 0.0));
+    assert!(n.dot(tangent).abs() < EPSILON);
+    assert_vec3_eq(n, Vec3::new(1.0, 2.0, 0.0).norm());
+
+    let m3 = Mat4::translation(Vec3::new(5.0, 5.0, 5.0)).mat3();
+    assert_eq!(m3.0, Mat3::identity().0);
+
+    let a = Mat3([[2.0, 0.0, 1.0], [1.0, 3.0, 0.0], [0.0, 1.0, 1.0]]);
+    let i = a * a.inverse().unwrap();
+    for r in 0..3 {
+        for c in 0..3 {
+            let expected = if r == c { 1.0 } else { 0.0 };
+            assert!((i.get(r, c) - expected).abs() < EPSILON, "{i:?}");
+        }
+    }
+}
+
+// ---- PROPERTY TESTS ----
+// a tiny xorshift generator, so the random cases are the same on every run
+struct Rng(u64);
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+    fn range(&mut self, min: f32, max: f32) -> f32 {
+        let t = (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32;
+        min + (max - min) * t
+    }
+    fn vec3(&mut self, min: f32, max: f32) -> Vec3<f32> {
+        Vec3::new(
+            self.range(min, max),
+            self.range(min, max),
+            self.range(min, max),
+        )
+    }
+    fn unit_vec3(&mut self) -> Vec3<f32> {
+        loop {
+            let v = self.vec3(-1.0, 1.0);
+            if v.len_sq() > 0.01 && v.len_sq() <= 1.0 {
+                return v.norm();
+            }
+        }
+    }
+    fn mat4(&mut self) -> Mat4 {
+        let mut m = Mat4::empty();
+        for i in 0..4 {
+            for j in 0..4 {
+                m.set(i, j, self.range(-2.0, 2.0));
+            }
+        }
+        m
+    }
+    /// A random well-conditioned transformation (translation, rotation, non-zero scaling).
+    fn transformation(&mut self) -> Mat4 {
+        let scale = self.vec3(0.5, 2.0);
+        Mat4::transformation(self.vec3(-10.0, 10.0), self.vec3(-PI, PI), scale)
+    }
+}
+const PI: f32 = std::f32::consts::PI;
+const CASES: usize = 256;
+
+fn assert_mat4_near(a: Mat4, b: Mat4, epsilon: f32) {
+    for i in 0..4 {
+        for j in 0..4 {
+            assert!(
+                (a.get(i, j) - b.get(i, j)).abs() < epsilon,
+                "element ({i}, {j}) differs:\n{a:?}\n{b:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn mat4_identity_is_neutral() {
+    let mut rng = Rng(0x9E3779B97F4A7C15);
+    for _ in 0..CASES {
+        let m = rng.mat4();
+        assert_mat4_eq(Mat4::identity() * m, m);
+        assert_mat4_eq(m * Mat4::identity(), m);
+    }
+}
+
+#[test]
+fn mat4_mul_is_associative() {
+    let mut rng = Rng(0xD1B54A32D192ED03);
+    for _ in 0..CASES {
+        let (a, b, c) = (rng.mat4(), rng.mat4(), rng.mat4());
+        assert_mat4_near((a * b) * c, a * (b * c), 1e-4);
+    }
+}
+
+#[test]
+fn mat4_mul_agrees_with_vec4_mul() {
+    let mut rng = Rng(0xA0761D6478BD642F);
+    for _ in 0..CASES {
+        let (a, b) = (rng.mat4(), rng.mat4());
+        let v = Vec4::new(
+            rng.range(-5.0, 5.0),
+            rng.range(-5.0, 5.0),
+            rng.range(-5.0, 5.0),
+            1.0,
+        );
+        let lhs = (a * b) * v;
+        let rhs = a * (b * v);
+        assert!((lhs - rhs).len() < 1e-3, "{lhs:?} != {rhs:?}");
+    }
+}
+
+#[test]
+fn mat4_transpose_properties() {
+    let mut rng = Rng(0xE7037ED1A0B428DB);
+    for _ in 0..CASES {
+        let (a, b) = (rng.mat4(), rng.mat4());
+        assert_mat4_eq(a.transpose().transpose(), a);
+        assert_mat4_near((a * b).transpose(), b.transpose() * a.transpose(), 1e-4);
+        assert!((a.transpose().determinant() - a.determinant()).abs() < 1e-3);
+    }
+}
+
+#[test]
+fn mat4_inverse_round_trip() {
+    let mut rng = Rng(0x8EBC6AF09C88C6E3);
+    for _ in 0..CASES {
+        let m = rng.transformation();
+        let inv = m.inverse().unwrap();
+        assert_mat4_near(m * inv, Mat4::identity(), 1e-4);
+        assert_mat4_near(inv * m, Mat4::identity(), 1e-4);
+
+        let p = rng.vec3(-10.0, 10.0);
+        let back = inv.transform_point(m.transform_point(p));
+        assert!((back - p).len() < 1e-3, "{back:?} != {p:?}");
+    }
+}
+
+#[test]
+fn mat4_inverse_of_product() {
+    let mut rng = Rng(0x589965CC75374CC3);
+    for _ in 0..CASES {
+        let (a, b) = (rng.transformation(), rng.transformation());
+        let lhs = (a * b).inverse().unwrap();
+        let rhs = b.inverse().unwrap() * a.inverse().unwrap();
+        assert_mat4_near(lhs, rhs, 1e-3);
+    }
+}
+
+#[test]
+fn rotations_are_orthogonal() {
+    let mut rng = Rng(0x1D8E4E27C47D124F);
+    for _ in 0..CASES {
+        let a = rng.range(-2.0 * PI, 2.0 * PI);
+        let q = Quat::from_axis_angle(rng.unit_vec3(), a);
+        for r in [
+            Mat4::x_rotation(a),
+            Mat4::y_rotation(a),
+            Mat4::z_rotation(a),
+            Mat4::rotation(q),
+        ] {
+            // the inverse of a rotation is its transpose, and it doesn't mirror
+            assert_mat4_near(r * r.transpose(), Mat4::identity(), 1e-5);
+            assert!((r.determinant() - 1.0).abs() < 1e-5, "{r:?}");
+        }
+    }
+}
+
+#[test]
+fn rotations_are_right_handed() {
+    let x = Vec3::new(1.0, 0.0, 0.0);
+    let y = Vec3::new(0.0, 1.0, 0.0);
+    let z = Vec3::new(0.0, 0.0, 1.0);
+    let quarter = PI / 2.0;
+
+    // a quarter turn counter-clockwise about each axis takes the next axis to the one after
+    assert_vec3_eq(Mat4::x_rotation(quarter).transform_dir(y), z);
+    assert_vec3_eq(Mat4::y_rotation(quarter).transform_dir(z), x);
+    assert_vec3_eq(Mat4::z_rotation(quarter).transform_dir(x), y);
+
+    for (axis, m) in [
+        (x, Mat4::x_rotation(quarter)),
+        (y, Mat4::y_rotation(quarter)),
+        (z, Mat4::z_rotation(quarter)),
+    ] {
+        assert_mat4_eq(Mat4::rotation(Quat::from_axis_angle(axis, quarter)), m);
+    }
+}
+
+#[test]
+fn quat_rotate_agrees_with_matrix() {
+    let mut rng = Rng(0x2545F4914F6CDD1D);
+    for _ in 0..CASES {
+        let q = Quat::from_axis_angle(rng.unit_vec3(), rng.range(-PI, PI));
+        let v = rng.vec3(-5.0, 5.0);
+        let a = q.rotate(v);
+        let b = Mat4::rotation(q).transform_dir(v);
+        assert!((a - b).len() < 1e-4, "{a:?} != {b:?}");
+        // rotating doesn't change length
+        assert!((a.len() - v.len()).abs() < 1e-4);
+    }
+}
+
+#[test]
+fn quat_from_euler_agrees_with_matrices() {
+    let mut rng = Rng(0x94D049BB133111EB);
+    for _ in 0..CASES {
+        let rot = rng.vec3(-PI, PI);
+        let m = Mat4::y_rotation(rot.y) * Mat4::x_rotation(rot.x) * Mat4::z_rotation(rot.z);
+        assert_mat4_near(Mat4::rotation(Quat::from_euler(rot)), m, 1e-5);
+    }
+}
+
+#[test]
+fn quat_slerp() {
+    let mut rng = Rng(0xBF58476D1CE4E5B9);
+    for _ in 0..CASES {
+        let axis = rng.unit_vec3();
+        let (a, b) = (rng.range(-PI, PI), rng.range(-PI, PI));
+        let qa = Quat::from_axis_angle(axis, a);
+        let qb = Quat::from_axis_angle(axis, b);
+
+        // the endpoints are the inputs (up to sign, q and -q are the same rotation)
+        assert!(Quat::slerp(qa, qb, 0.0).dot(qa).abs() > 1.0 - 1e-5);
+        assert!(Quat::slerp(qa, qb, 1.0).dot(qb).abs() > 1.0 - 1e-5);
+
+        // halfway between two rotations about the same axis is the rotation by the average angle,
+        // unless the short way around crosses over +-PI
+        if (a - b).abs() < PI {
+            let mid = Quat::slerp(qa, qb, 0.5);
+            let expected = Quat::from_axis_angle(axis, (a + b) / 2.0);
+            assert!(
+                mid.dot(expected).abs() > 1.0 - 1e-5,
+                "{mid:?} != {expected:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn projection_maps_near_and_far_to_wgpu_depth_range() {
+    let (near, far) = (0.1, 500.0);
+    let m = Mat4::projection(70f32.to_radians(), 16.0 / 9.0, near, far);
+
+    // wgpu's clip space depth is 0..1, not OpenGL's -1..1
+    let depth = |z: f32| m.transform_point(Vec3::new(0.0, 0.0, z)).z;
+    assert!(depth(-near).abs() < EPSILON, "{}", depth(-near));
+    assert!((depth(-far) - 1.0).abs() < EPSILON, "{}", depth(-far));
+
+    // depth increases monotonically with distance, and stays in range
+    let mut last = depth(-near);
+    for i in 1..100 {
+        let z = -(near + (far - near) * i as f32 / 100.0);
+        let d = depth(z);
+        assert!(d > last && (0.0..=1.0).contains(&d), "depth({z}) = {d}");
+        last = d;
+    }
+
+    // a point on the edge of the horizontal field of view lands on the edge of the screen
+    let edge = Vec3::new(35f32.to_radians().tan(), 0.0, -1.0);
+    assert!((m.transform_point(edge).x - 1.0).abs() < EPSILON);
+}
+
+// ---- REFERENCE IMPLEMENTATION ----
+// a straightforward, f64, row-major implementation, written from the textbook formulas
+// independently of `Mat4`'s storage layout, to check `Mat4` against
+mod reference {
+    pub type M = [[f64; 4]; 4];
+
+    pub fn identity() -> M {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            row[i] = 1.0;
+        }
+        m
+    }
+
+    pub fn mul(a: &M, b: &M) -> M {
+        let mut out = [[0.0; 4]; 4];
+        for i in 0..4 {
+            for j in 0..4 {
+                out[i][j] = (0..4).map(|k| a[i][k] * b[k][j]).sum();
+            }
+        }
+        out
+    }
+
+    pub fn translation(t: [f64; 3]) -> M {
+        let mut m = identity();
+        m[0][3] = t[0];
+        m[1][3] = t[1];
+        m[2][3] = t[2];
+        m
+    }
+
+    pub fn scaling(s: [f64; 3]) -> M {
+        let mut m = identity();
+        m[0][0] = s[0];
+        m[1][1] = s[1];
+        m[2][2] = s[2];
+        m
+    }
+
+    /// Rodrigues' rotation formula: R = I + sin(a) K + (1 - cos(a)) K^2
+    pub fn axis_angle(axis: [f64; 3], a: f64) -> M {
+        let [x, y, z] = axis;
+        let k = [[0.0, -z, y], [z, 0.0, -x], [-y, x, 0.0]];
+        let mut m = identity();
+        for i in 0..3 {
+            for j in 0..3 {
+                let k2: f64 = (0..3).map(|n| k[i][n] * k[n][j]).sum();
+                m[i][j] += a.sin() * k[i][j] + (1.0 - a.cos()) * k2;
+            }
+        }
+        m
+    }
+
+    /// Right-handed perspective projection to a 0..1 depth range, from the horizontal fov.
+    pub fn perspective(hfov: f64, aspect: f64, near: f64, far: f64) -> M {
+        let w = 1.0 / (hfov / 2.0).tan();
+        let h = w * aspect;
+        let mut m = [[0.0; 4]; 4];
+        m[0][0] = w;
+        m[1][1] = h;
+        m[2][2] = far / (near - far);
+        m[2][3] = near * far / (near - far);
+        m[3][2] = -1.0;
+        m
+    }
+}
+
+fn to_reference(m: Mat4) -> reference::M {
+    let mut out = [[0.0; 4]; 4];
+    for (i, row) in out.iter_mut().enumerate() {
+        for (j, e) in row.iter_mut().enumerate() {
+            *e = m.get(i, j) as f64;
+        }
+    }
+    out
+}
+
+fn assert_matches_reference(m: Mat4, r: reference::M, epsilon: f64) {
+    for (i, row) in r.iter().enumerate() {
+        for (j, e) in row.iter().enumerate() {
+            assert!(
+                (m.get(i, j) as f64 - e).abs() < epsilon,
+                "element ({i}, {j}) differs:\n{m:?}\n{r:?}"
+            );
+        }
+    }
+}
+
+fn f64x3(v: Vec3<f32>) -> [f64; 3] {
+    [v.x as f64, v.y as f64, v.z as f64]
+}
+
+#[test]
+fn mat4_mul_matches_reference() {
+    let mut rng = Rng(0x6A09E667F3BCC909);
+    for _ in 0..CASES {
+        let (a, b) = (rng.mat4(), rng.mat4());
+        let r = reference::mul(&to_reference(a), &to_reference(b));
+        assert_matches_reference(a * b, r, 1e-4);
+    }
+}
+
+#[test]
+fn rotations_match_reference() {
+    let mut rng = Rng(0xBB67AE8584CAA73B);
+    let axes = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+    for _ in 0..CASES {
+        let a = rng.range(-2.0 * PI, 2.0 * PI);
+        let rotations = [
+            Mat4::x_rotation(a),
+            Mat4::y_rotation(a),
+            Mat4::z_rotation(a),
+        ];
+        for (m, axis) in rotations.into_iter().zip(axes) {
+            assert_matches_reference(m, reference::axis_angle(axis, a as f64), 1e-5);
+        }
+
+        let axis = rng.unit_vec3();
+        let q = Quat::from_axis_angle(axis, a);
+        let r = reference::axis_angle(f64x3(axis), a as f64);
+        assert_matches_reference(Mat4::rotation(q), r, 1e-5);
+    }
+}
+
+#[test]
+fn transformation_matches_reference() {
+    let mut rng = Rng(0x3C6EF372FE94F82B);
+    for _ in 0..CASES {
+        let (t, rot, s) = (rng.vec3(-10.0, 10.0), rng.vec3(-PI, PI), rng.vec3(0.5, 2.0));
+        let r = [
+            reference::translation(f64x3(t)),
+            reference::axis_angle([1.0, 0.0, 0.0], rot.x as f64),
+            reference::axis_angle([0.0, 1.0, 0.0], rot.y as f64),
+            reference::axis_angle([0.0, 0.0, 1.0], rot.z as f64),
+            reference::scaling(f64x3(s)),
+        ]
+        .iter()
+        .fold(reference::identity(), |acc, m| reference::mul(&acc, m));
+        assert_matches_reference(Mat4::transformation(t, rot, s), r, 1e-4);
+    }
+}
+
+#[test]
+fn projection_matches_reference() {
+    let mut rng = Rng(0xA54FF53A5F1D36F1);
+    for _ in 0..CASES {
+        let fov = rng.range(30.0, 120.0).to_radians();
+        let aspect = rng.range(0.5, 3.0);
+        let near = rng.range(0.01, 1.0);
+        let far = rng.range(10.0, 1000.0);
+        let r = reference::perspective(fov as f64, aspect as f64, near as f64, far as f64);
+        assert_matches_reference(Mat4::projection(fov, aspect, near, far), r, 1e-4);
+    }
+}
+
+// ---- SIMD ----
+// with the `simd` feature, the operators use SSE, which must agree with the scalar code
+
+#[test]
+fn mat4_operators_match_scalar() {
+    let mut rng = Rng(0x510E527FADE682D1);
+    for _ in 0..CASES {
+        let (a, b) = (rng.mat4(), rng.mat4());
+        assert_mat4_near(a * b, a.mul_scalar(&b), 1e-5);
+        assert_mat4_eq(a.transpose(), a.transpose_scalar());
+
+        let v = Vec4::new(
+            rng.range(-5.0, 5.0),
+            rng.range(-5.0, 5.0),
+            rng.range(-5.0, 5.0),
+            1.0,
+        );
+        let (lhs, rhs) = (a * v, a.mul_vec4_scalar(v));
+        assert!((lhs - rhs).len() < 1e-5, "{lhs:?} != {rhs:?}");
+    }
+}
+
+#[test]
+fn vec4_operators_match_scalar() {
+    let mut rng = Rng(0x9B05688C2B3E6C1F);
+    let mut vec4 = || {
+        Vec4::new(
+            rng.range(-5.0, 5.0),
+            rng.range(-5.0, 5.0),
+            rng.range(-5.0, 5.0),
+            rng.range(0.5, 5.0),
+        )
+    };
+    for _ in 0..CASES {
+        let (a, b) = (vec4(), vec4());
+        // component-wise SSE operations are exactly the scalar ones
+        assert_eq!(a + b, a.add_scalar(b));
+        assert_eq!(a - b, a.sub_scalar(b));
+        assert_eq!(a * b, a.mul_scalar(b));
+        assert_eq!(a / b, a.div_scalar(b));
+        assert_eq!(a * 2.5, a.mul_scalar(Vec4::all(2.5)));
+        // but the dot product adds the products in a different order
+        assert!((a.dot(b) - a.dot_scalar(b)).abs() < 1e-4);
+    }
+}
+
+#[test]
+fn reverse_z_projections() {
+    let (near, far) = (0.1, 500.0);
+    let fov = 70f32.to_radians();
+    let depth = |m: Mat4, z: f32| m.transform_point(Vec3::new(0.0, 0.0, z)).z;
+
+    let m = Mat4::projection_reverse_z(fov, 1.5, near, far);
+    assert!((depth(m, -near) - 1.0).abs() < EPSILON);
+    assert!(depth(m, -far).abs() < EPSILON);
+    // x and y are the same as the regular projection
+    let p = Vec3::new(3.0, -2.0, -10.0);
+    let regular = Mat4::projection(fov, 1.5, near, far).transform_point(p);
+    let reversed = m.transform_point(p);
+    assert_vec3_eq(
+        Vec3::new(reversed.x, reversed.y, 0.0),
+        Vec3::new(regular.x, regular.y, 0.0),
+    );
+    assert!((reversed.z - (1.0 - regular.z)).abs() < 1e-4);
+
+    let m = Mat4::projection_infinite_reverse_z(fov, 1.5, near);
+    assert!((depth(m, -near) - 1.0).abs() < EPSILON);
+    assert!(depth(m, -1e7) < 1e-6 && depth(m, -1e7) > 0.0);
+    let mut last = depth(m, -near);
+    for z in [-1.0, -10.0, -100.0, -1000.0, -10000.0] {
+        assert!(depth(m, z) < last);
+        last = depth(m, z);
+    }
+
+    let m = Mat4::projection_infinite(fov, 1.5, near);
+    assert!(depth(m, -near).abs() < EPSILON);
+    assert!((depth(m, -1e7) - 1.0).abs() < 1e-6);
+}
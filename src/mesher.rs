@@ -0,0 +1,740 @@
+use crate::block::{Block, RenderLayer, ATLAS_SIZE};
+use crate::chunk::{chunk_origin, Chunk, CHUNK_SIZE};
+use crate::fluid;
+use crate::light::{LightKind, MAX_LIGHT};
+use crate::math::Vec3;
+use crate::model::{Face, QUAD_INDICES, QUAD_INDICES_FLIPPED};
+use crate::settings::Settings;
+use crate::world::World;
+use crate::{uniform_bind_layout, Uniform};
+use wgpu::util::DeviceExt;
+
+/// A chunk mesh vertex, packed into two `u32`s (8 bytes, vs 32 for `model::Vertex`).
+/// Unpacked by `vs_main` in `chunk.wgsl`, which must be kept in sync with the layout here.
+///
+/// `data.0`:
+/// - bits 0..15: X, Y and Z, relative to the chunk origin (5 bits each, so 0..=16)
+/// - bits 15..18: the `Face` the vertex belongs to
+/// - bits 18..20: which corner of the face the vertex is (an index into `model::FACE_TEX_COORDS`)
+/// - bits 20..32: the texture, an index into the block atlas
+///
+/// `data.1`:
+/// - bits 0..2: ambient occlusion, 0 is fully occluded, 3 is not occluded
+/// - bits 2..6: sky light, 0..=15
+/// - bits 6..10: block light, 0..=15
+/// - bits 10..14: how far below its position the vertex is, in 16ths of a block. Fluid surfaces
+///   are lower than the top of their block
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct PackedVertex(pub u32, pub u32);
+
+/// The fields of a `PackedVertex`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct VertexData {
+    pub pos: Vec3<u32>,
+    pub face: Face,
+    pub corner: u32,
+    pub texture: u32,
+    pub ao: u32,
+    pub sky_light: u32,
+    pub block_light: u32,
+    pub lowered: u32,
+}
+
+impl PackedVertex {
+    pub const ATTRIBS: [wgpu::VertexAttribute; 1] = wgpu::vertex_attr_array![0 => Uint32x2];
+
+    pub fn buffer_layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Self>() as u64,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBS,
+        }
+    }
+
+    pub fn pack(v: VertexData) -> Self {
+        debug_assert!(v.pos.x <= 16 && v.pos.y <= 16 && v.pos.z <= 16, "{v:?}");
+        debug_assert!(v.corner < 4 && v.texture < 1 << 12, "{v:?}");
+        debug_assert!(v.ao < 4 && v.sky_light < 16 && v.block_light < 16, "{v:?}");
+        debug_assert!(v.lowered < 16, "{v:?}");
+        Self(
+            v.pos.x
+                | v.pos.y << 5
+                | v.pos.z << 10
+                | (v.face as u32) << 15
+                | v.corner << 18
+                | v.texture << 20,
+            v.ao | v.sky_light << 2 | v.block_light << 6 | v.lowered << 10,
+        )
+    }
+
+    pub fn unpack(self) -> VertexData {
+        VertexData {
+            pos: Vec3::new(self.0 & 31, (self.0 >> 5) & 31, (self.0 >> 10) & 31),
+            face: Face::from_index((self.0 >> 15) & 7),
+            corner: (self.0 >> 18) & 3,
+            texture: self.0 >> 20,
+            ao: self.1 & 3,
+            sky_light: (self.1 >> 2) & 15,
+            block_light: (self.1 >> 6) & 15,
+            lowered: (self.1 >> 10) & 15,
+        }
+    }
+}
+
+// every texture index must fit in the 12 bits the packed vertex has for it
+const _: () = assert!(ATLAS_SIZE * ATLAS_SIZE <= 1 << 12);
+
+/// The faces of one `RenderLayer` of a chunk.
+/// Every face is a quad of 4 consecutive vertices, and 6 consecutive indices.
+#[derive(Default, Clone, Debug)]
+pub struct ChunkMeshData {
+    pub vertices: Vec<PackedVertex>,
+    pub indices: Vec<u32>,
+}
+impl ChunkMeshData {
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// Reorders the faces so the ones furthest from `pos` (relative to the chunk origin) come
+    /// first. Translucent faces blend with what's already drawn behind them, so they have to be
+    /// drawn from back to front.
+    pub fn sort_back_to_front(&mut self, pos: Vec3<f32>) {
+        let vertices = &self.vertices;
+        let dist = |face: &[u32]| {
+            // the corners of a face are the 4 vertices starting at the lowest index
+            let first = face.iter().min().copied().unwrap_or(0) as usize;
+            let center = vertices[first..first + 4]
+                .iter()
+                .fold(Vec3::all(0.0), |sum, v| {
+                    sum + v.unpack().pos.map(|e| e as f32)
+                })
+                / 4.0;
+            (center - pos).len_sq()
+        };
+        let mut faces: Vec<_> = self
+            .indices
+            .chunks_exact(6)
+            .map(|face| (dist(face), <[u32; 6]>::try_from(face).unwrap()))
+            .collect();
+        faces.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+        self.indices = faces.into_iter().flat_map(|(_, face)| face).collect();
+    }
+}
+
+/// The mesh of every `RenderLayer` of a chunk, indexed by layer.
+pub type ChunkLayers<T> = [T; RenderLayer::ALL.len()];
+
+/// One layer of a chunk mesh uploaded to the GPU.
+pub struct LayerMesh {
+    pub vertex_buffer: wgpu::Buffer,
+    pub index_buffer: wgpu::Buffer,
+    pub num_indices: u32,
+}
+impl LayerMesh {
+    fn new(device: &wgpu::Device, data: &ChunkMeshData) -> Option<Self> {
+        if data.is_empty() {
+            return None;
+        }
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("chunk vertex buffer"),
+            contents: bytemuck::cast_slice(&data.vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("chunk index buffer"),
+            contents: bytemuck::cast_slice(&data.indices),
+            // rewritten when translucent faces are sorted
+            usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
+        });
+        Some(Self {
+            vertex_buffer,
+            index_buffer,
+            num_indices: data.indices.len() as u32,
+        })
+    }
+}
+
+/// A chunk mesh uploaded to the GPU.
+pub struct ChunkMesh {
+    pub layers: ChunkLayers<Option<LayerMesh>>,
+    // the layers that are blended, kept to sort their faces again when the camera moves
+    blended: Vec<(RenderLayer, ChunkMeshData)>,
+    // the block the camera was in (relative to the chunk origin) when the blended faces
+    // were last sorted
+    sorted_for: Option<Vec3<i32>>,
+    // the world position of the chunk, w is unused
+    pub origin: Uniform<[f32; 4]>,
+    pub bind_group: wgpu::BindGroup,
+}
+impl ChunkMesh {
+    /// The layout of the bind group that supplies the chunk origin to `chunk.wgsl`.
+    pub fn bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("chunk_bind_group_layout"),
+            entries: &[uniform_bind_layout(0, wgpu::ShaderStages::VERTEX)],
+        })
+    }
+
+    pub fn new(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        chunk_pos: Vec3<i32>,
+        data: ChunkLayers<ChunkMeshData>,
+    ) -> Self {
+        let layers = RenderLayer::ALL.map(|layer| LayerMesh::new(device, &data[layer as usize]));
+        let blended = (RenderLayer::ALL.into_iter().zip(data))
+            .filter(|(layer, data)| layer.is_blended() && !data.is_empty())
+            .collect();
+
+        let origin = chunk_origin(chunk_pos).as_f32();
+        let origin = Uniform::new(device, [origin.x, origin.y, origin.z, 0.0]);
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("chunk_bind_group"),
+            layout,
+            entries: &[origin.binding(0)],
+        });
+        Self {
+            layers,
+            blended,
+            sorted_for: None,
+            origin,
+            bind_group,
+        }
+    }
+
+    /// Sorts the faces of the blended layers from back to front as seen from `cam_pos`.
+    /// They're only sorted again once the camera moves into another block.
+    pub fn sort_blended(&mut self, queue: &wgpu::Queue, cam_pos: Vec3<f32>) {
+        let origin = Vec3::new(
+            self.origin.data[0],
+            self.origin.data[1],
+            self.origin.data[2],
+        );
+        let local_pos = cam_pos - origin;
+        let block = local_pos.floor_i32();
+        if self.sorted_for == Some(block) {
+            return;
+        }
+        for (layer, data) in &mut self.blended {
+            let Some(mesh) = &self.layers[*layer as usize] else {
+                continue;
+            };
+            data.sort_back_to_front(local_pos);
+            queue.write_buffer(&mesh.index_buffer, 0, bytemuck::cast_slice(&data.indices));
+        }
+        self.sorted_for = Some(block);
+    }
+
+    /// Draws the faces in `layer`, if there are any.
+    pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, layer: RenderLayer) {
+        let Some(mesh) = &self.layers[layer as usize] else {
+            return;
+        };
+        render_pass.set_bind_group(2, &self.bind_group, &[]);
+        render_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+        render_pass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        render_pass.draw_indexed(0..mesh.num_indices, 0, 0..1);
+    }
+}
+
+/// The size of a chunk, plus a one block border on every side.
+const PADDED_SIZE: i32 = CHUNK_SIZE + 2;
+
+/// A copy of the blocks in a chunk, and the blocks bordering it from its 26 neighbors.
+/// Meshing needs to look at the blocks around every block, and looking them up through
+/// the world's chunk map every time would be slow.
+pub struct Neighborhood {
+    blocks: Vec<Block>,
+    // packed like `Chunk` stores it
+    light: Vec<u8>,
+    levels: Vec<u8>,
+}
+impl Neighborhood {
+    pub fn new(world: &World, chunk_pos: Vec3<i32>) -> Self {
+        let volume = (PADDED_SIZE * PADDED_SIZE * PADDED_SIZE) as usize;
+        let mut neighborhood = Self {
+            blocks: vec![Block::AIR; volume],
+            // chunks that aren't loaded are treated as open sky
+            light: vec![MAX_LIGHT << 4; volume],
+            levels: vec![0; volume],
+        };
+        for cy in -1..=1 {
+            for cz in -1..=1 {
+                for cx in -1..=1 {
+                    let offset = Vec3::new(cx, cy, cz);
+                    let Some(chunk) = world.chunk(chunk_pos + offset) else {
+                        continue;
+                    };
+                    neighborhood.copy_from(chunk, offset);
+                }
+            }
+        }
+        neighborhood
+    }
+
+    /// Copies the part of `chunk` that overlaps the padded area. `offset` is the position of
+    /// `chunk` relative to the center chunk.
+    fn copy_from(&mut self, chunk: &Chunk, offset: Vec3<i32>) {
+        // the range of local positions in `chunk` that fall inside the padded area
+        let range = |o: i32| match o {
+            -1 => CHUNK_SIZE - 1..CHUNK_SIZE,
+            0 => 0..CHUNK_SIZE,
+            _ => 0..1,
+        };
+        for y in range(offset.y) {
+            for z in range(offset.z) {
+                for x in range(offset.x) {
+                    let local = Vec3::new(x, y, z);
+                    let pos = local + offset * CHUNK_SIZE;
+                    let index = Self::index(pos);
+                    self.blocks[index] = chunk.get(local);
+                    self.light[index] = chunk.packed_light(local);
+                    self.levels[index] = chunk.level(local);
+                }
+            }
+        }
+    }
+
+    /// Expects every element of `pos` to be in `-1..=CHUNK_SIZE`.
+    #[inline(always)]
+    fn index(pos: Vec3<i32>) -> usize {
+        let p = pos + 1;
+        (p.x + p.z * PADDED_SIZE + p.y * PADDED_SIZE * PADDED_SIZE) as usize
+    }
+
+    /// The block at `pos`, relative to the center chunk's origin.
+    /// Expects every element of `pos` to be in `-1..=CHUNK_SIZE`.
+    #[inline(always)]
+    pub fn get(&self, pos: Vec3<i32>) -> Block {
+        self.blocks[Self::index(pos)]
+    }
+
+    /// The fluid level at `pos`, relative to the center chunk's origin, see `fluid`.
+    /// Expects every element of `pos` to be in `-1..=CHUNK_SIZE`.
+    #[inline(always)]
+    pub fn level(&self, pos: Vec3<i32>) -> u8 {
+        self.levels[Self::index(pos)]
+    }
+
+    /// The light level of `kind` at `pos`, relative to the center chunk's origin.
+    /// Expects every element of `pos` to be in `-1..=CHUNK_SIZE`.
+    #[inline(always)]
+    pub fn light(&self, pos: Vec3<i32>, kind: LightKind) -> u8 {
+        let light = self.light[Self::index(pos)];
+        match kind {
+            LightKind::Sky => light >> 4,
+            LightKind::Block => light & 0xF,
+        }
+    }
+}
+
+/// Builds the mesh of the chunk at `chunk_pos`.
+/// Only faces that aren't hidden behind an opaque neighbor are included.
+/// Each face goes in the mesh of its block's `RenderLayer`.
+pub fn mesh_chunk(
+    world: &World,
+    chunk_pos: Vec3<i32>,
+    settings: &Settings,
+) -> ChunkLayers<ChunkMeshData> {
+    let mut meshes = ChunkLayers::<ChunkMeshData>::default();
+    let Some(chunk) = world.chunk(chunk_pos) else {
+        return meshes;
+    };
+    if chunk.is_empty() {
+        return meshes;
+    }
+    let blocks = Neighborhood::new(world, chunk_pos);
+
+    for y in 0..CHUNK_SIZE {
+        for z in 0..CHUNK_SIZE {
+            for x in 0..CHUNK_SIZE {
+                let pos = Vec3::new(x, y, z);
+                let block = blocks.get(pos);
+                if block.is_air() {
+                    continue;
+                }
+                let mesh = &mut meshes[block.info().layer as usize];
+                for face in Face::ALL {
+                    let neighbor = blocks.get(pos + face.normal());
+                    // faces between two blocks of glass or water are hidden too,
+                    // so that they look like one big block. Unless the neighbor is shallower
+                    // fluid, which leaves the side of this one showing above it
+                    let hidden = neighbor.info().opaque
+                        || (neighbor == block
+                            && (face.normal().y != 0
+                                || !block.info().liquid
+                                || fluid_height(&blocks, pos + face.normal())
+                                    >= fluid_height(&blocks, pos)));
+                    if hidden {
+                        continue;
+                    }
+                    add_face(mesh, &blocks, settings, pos, face, block);
+                }
+            }
+        }
+    }
+    meshes
+}
+
+/// The ambient occlusion of a face corner, from the three blocks touching it in front of the face.
+/// 0 is fully occluded, 3 is not occluded at all.
+/// If both sides are occluded, the corner is hidden no matter what the corner block is.
+pub fn vertex_ao(side1: bool, side2: bool, corner: bool) -> u32 {
+    if side1 && side2 {
+        return 0;
+    }
+    3 - (side1 as u32 + side2 as u32 + corner as u32)
+}
+
+/// The offsets from the block in front of a face to the blocks touching the corner of the face
+/// at `vert`: the two blocks on either side of it, and the one diagonal to it.
+fn corner_neighbors(pos: Vec3<i32>, face: Face, vert: Vec3<i32>) -> [Vec3<i32>; 3] {
+    let normal = face.normal();
+    // points from the center of the face towards the corner, along the face
+    let dir = (vert - pos) * 2 - 1;
+    let dir = Vec3::new(
+        if normal.x == 0 { dir.x } else { 0 },
+        if normal.y == 0 { dir.y } else { 0 },
+        if normal.z == 0 { dir.z } else { 0 },
+    );
+    // split `dir` into its two non-zero components
+    let (side1, side2) = match normal {
+        Vec3 { x: 0, y: 0, .. } => (Vec3::new(dir.x, 0, 0), Vec3::new(0, dir.y, 0)),
+        Vec3 { x: 0, .. } => (Vec3::new(dir.x, 0, 0), Vec3::new(0, 0, dir.z)),
+        _ => (Vec3::new(0, dir.y, 0), Vec3::new(0, 0, dir.z)),
+    };
+    [side1, side2, dir]
+}
+
+/// The light level of `kind` at a face corner, the average of the (up to) four blocks touching
+/// it in front of the face. Opaque blocks have no light of their own, so they are left out,
+/// the darkening next to them comes from the AO instead.
+fn corner_light(
+    blocks: &Neighborhood,
+    front: Vec3<i32>,
+    neighbors: [Vec3<i32>; 3],
+    kind: LightKind,
+) -> u32 {
+    let [side1, side2, corner] = neighbors.map(|offset| front + offset);
+    let transparent = |pos: Vec3<i32>| !blocks.get(pos).info().opaque;
+
+    let mut total = blocks.light(front, kind) as u32;
+    let mut count = 1;
+    for pos in [side1, side2] {
+        if transparent(pos) {
+            total += blocks.light(pos, kind) as u32;
+            count += 1;
+        }
+    }
+    // light can't reach the corner block through two opaque sides
+    if count > 1 && transparent(corner) {
+        total += blocks.light(corner, kind) as u32;
+        count += 1;
+    }
+    // rounded to the nearest level
+    (total + count / 2) / count
+}
+
+/// How high the fluid at `pos` reaches, in 16ths of a block.
+/// Fluids fill their block when there's more of the same fluid on top, otherwise their surface
+/// depends on their level.
+fn fluid_height(blocks: &Neighborhood, pos: Vec3<i32>) -> u32 {
+    if blocks.get(pos + Vec3::new(0, 1, 0)) == blocks.get(pos) {
+        16
+    } else {
+        fluid::height(blocks.level(pos))
+    }
+}
+
+fn add_face(
+    mesh: &mut ChunkMeshData,
+    blocks: &Neighborhood,
+    settings: &Settings,
+    pos: Vec3<i32>,
+    face: Face,
+    block: Block,
+) {
+    let verts = face.verts(pos, pos + 1);
+    let neighbors = verts.map(|vert| corner_neighbors(pos, face, vert));
+    // a face is lit by the light in the block in front of it
+    let front = pos + face.normal();
+    let occludes = |offset: Vec3<i32>| blocks.get(front + offset).info().opaque;
+    let ao = neighbors.map(|[side1, side2, corner]| {
+        vertex_ao(occludes(side1), occludes(side2), occludes(corner))
+    });
+    let light = |kind: LightKind| match settings.smooth_lighting {
+        true => neighbors.map(|neighbors| corner_light(blocks, front, neighbors, kind)),
+        false => [blocks.light(front, kind) as u32; 4],
+    };
+    let sky_light = light(LightKind::Sky);
+    let block_light = light(LightKind::Block);
+    let texture = block.info().textures.get(face) as u32;
+    // only the top of a fluid is lowered, its bottom stays on the block below
+    let lowered = match block.info().liquid {
+        true => 16 - fluid_height(blocks, pos),
+        false => 0,
+    };
+
+    let first = mesh.vertices.len() as u32;
+    for (corner, vert) in verts.into_iter().enumerate() {
+        mesh.vertices.push(PackedVertex::pack(VertexData {
+            pos: vert.map(|e| e as u32),
+            face,
+            corner: corner as u32,
+            texture,
+            ao: ao[corner],
+            sky_light: sky_light[corner],
+            block_light: block_light[corner],
+            lowered: if vert.y > pos.y { lowered } else { 0 },
+        }));
+    }
+    // the AO is interpolated across each triangle, so a quad split along the diagonal
+    // between its darker corners would look different from one split along the other,
+    // and darkness would spread out in a way that depends on the orientation of the face
+    let indices = if ao[0] + ao[2] < ao[1] + ao[3] {
+        QUAD_INDICES_FLIPPED
+    } else {
+        QUAD_INDICES
+    };
+    mesh.indices.extend(indices.map(|i| first + i as u32));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pack_unpack_round_trip() {
+        let mut checked = 0;
+        for face in Face::ALL {
+            for corner in 0..4 {
+                for (x, y, z) in [(0, 0, 0), (16, 16, 16), (3, 15, 9), (16, 0, 7)] {
+                    for (texture, ao, sky_light, block_light, lowered) in [
+                        (0, 0, 0, 0, 0),
+                        (4095, 3, 15, 15, 15),
+                        (17, 2, 9, 4, 2),
+                        (256, 1, 0, 15, 7),
+                    ] {
+                        let data = VertexData {
+                            pos: Vec3::new(x, y, z),
+                            face,
+                            corner,
+                            texture,
+                            ao,
+                            sky_light,
+                            block_light,
+                            lowered,
+                        };
+                        assert_eq!(PackedVertex::pack(data).unpack(), data);
+                        checked += 1;
+                    }
+                }
+            }
+        }
+        assert_eq!(checked, 6 * 4 * 4 * 4);
+    }
+
+    #[test]
+    fn packed_vertex_is_small() {
+        assert_eq!(std::mem::size_of::<PackedVertex>(), 8);
+    }
+
+    /// The opaque faces of the chunk at `pos`.
+    fn mesh_opaque(world: &World, pos: Vec3<i32>, settings: &Settings) -> ChunkMeshData {
+        let [opaque, _, _, _] = mesh_chunk(world, pos, settings);
+        opaque
+    }
+
+    #[test]
+    fn hidden_faces_are_culled() {
+        let num_faces =
+            |world: &World, pos| mesh_opaque(world, pos, &Settings::default()).indices.len() / 6;
+        let mut world = World::empty(0);
+        world.set_block(Vec3::new(0, 0, 0), Block::STONE);
+        assert_eq!(num_faces(&world, Vec3::all(0)), 6);
+
+        // two blocks next to each other hide one face each
+        world.set_block(Vec3::new(1, 0, 0), Block::STONE);
+        assert_eq!(num_faces(&world, Vec3::all(0)), 10);
+
+        // the same goes for blocks in neighboring chunks
+        world.set_block(Vec3::new(-1, 0, 0), Block::STONE);
+        assert_eq!(num_faces(&world, Vec3::all(0)), 9);
+        assert_eq!(num_faces(&world, Vec3::new(-1, 0, 0)), 5);
+    }
+
+    #[test]
+    fn faces_go_in_the_layer_of_their_block() {
+        let mut world = World::empty(0);
+        world.set_block(Vec3::new(0, 0, 0), Block::STONE);
+        world.set_block(Vec3::new(2, 0, 0), Block::LEAVES);
+        world.set_block(Vec3::new(4, 0, 0), Block::WATER);
+        world.set_block(Vec3::new(5, 0, 0), Block::WATER);
+        world.set_block(Vec3::new(8, 0, 0), Block::ICE);
+        let [opaque, cutout, translucent, water] =
+            mesh_chunk(&world, Vec3::all(0), &Settings::default());
+        assert_eq!(opaque.indices.len() / 6, 6);
+        assert_eq!(cutout.indices.len() / 6, 6);
+        assert_eq!(translucent.indices.len() / 6, 6);
+        // the faces between the two water blocks are hidden
+        assert_eq!(water.indices.len() / 6, 10);
+
+        // faces of opaque blocks can be seen through the water next to them
+        world.set_block(Vec3::new(6, 0, 0), Block::STONE);
+        let [opaque, _, _, water] = mesh_chunk(&world, Vec3::all(0), &Settings::default());
+        assert_eq!(opaque.indices.len() / 6, 12);
+        assert_eq!(water.indices.len() / 6, 9);
+    }
+
+    #[test]
+    fn translucent_faces_are_sorted_back_to_front() {
+        let mut world = World::empty(0);
+        for x in 0..8 {
+            world.set_block(Vec3::new(x * 2, 3, 5), Block::ICE);
+        }
+        let [_, _, mut mesh, _] = mesh_chunk(&world, Vec3::all(0), &Settings::default());
+        let face_dists = |mesh: &ChunkMeshData, pos: Vec3<f32>| {
+            (mesh.indices.chunks(6))
+                .map(|face| {
+                    // the two triangles share two of the corners
+                    let mut corners = face.to_vec();
+                    corners.sort();
+                    corners.dedup();
+                    let center = (corners.iter()).fold(Vec3::all(0.0), |sum, &i| {
+                        sum + mesh.vertices[i as usize].unpack().pos.map(|e| e as f32)
+                    }) / 4.0;
+                    (center - pos).len_sq()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        for pos in [Vec3::new(0.5, 3.5, 0.5), Vec3::new(15.5, 10.0, 5.5)] {
+            mesh.sort_back_to_front(pos);
+            let dists = face_dists(&mesh, pos);
+            assert_eq!(dists.len(), 8 * 6);
+            assert!(dists.windows(2).all(|w| w[0] >= w[1]), "{dists:?}");
+        }
+    }
+
+    #[test]
+    fn fluid_surfaces_are_lowered() {
+        let mut world = World::empty(0);
+        world.set_block(Vec3::new(0, 0, 0), Block::WATER);
+        world.set_block(Vec3::new(0, 1, 0), Block::WATER);
+        let [_, _, _, water] = mesh_chunk(&world, Vec3::all(0), &Settings::default());
+        for v in water.vertices.iter().map(|v| v.unpack()) {
+            // only the top of the upper block is lowered, the water below it is full
+            let expected = if v.pos.y == 2 { 2 } else { 0 };
+            assert_eq!(v.lowered, expected, "{v:?}");
+        }
+    }
+
+    #[test]
+    fn fluid_sides_show_above_shallower_fluid() {
+        let mut world = World::empty(0);
+        world.set_block(Vec3::new(0, 0, 0), Block::WATER);
+        world.set_fluid(Vec3::new(1, 0, 0), Block::WATER, 4);
+        let [_, _, _, water] = mesh_chunk(&world, Vec3::all(0), &Settings::default());
+        let faces: Vec<_> = (water.vertices.iter().map(|v| v.unpack()))
+            .filter(|v| v.pos.x == 1 && v.face.normal().y == 0 && v.face.normal().z == 0)
+            .map(|v| v.face)
+            .collect();
+        // the source's side shows above the flowing water, but not the other way around
+        assert_eq!(faces, [Face::PosX; 4]);
+    }
+
+    /// The vertices of the top faces at `y`, sorted by position.
+    fn top_vertices(world: &World, y: u32, settings: &Settings) -> Vec<VertexData> {
+        let mesh = mesh_opaque(world, Vec3::all(0), settings);
+        let mut vertices: Vec<_> = (mesh.vertices.iter().map(|v| v.unpack()))
+            .filter(|v| v.face == Face::PosY && v.pos.y == y)
+            .collect();
+        vertices.sort_by_key(|v| (v.pos.x, v.pos.z));
+        vertices
+    }
+
+    #[test]
+    fn corners_next_to_blocks_are_occluded() {
+        let mut world = World::empty(0);
+        world.set_block(Vec3::new(0, 0, 0), Block::STONE);
+        let ao_of_top = |world: &World| {
+            let vertices = top_vertices(world, 1, &Settings::default());
+            vertices.iter().map(|v| v.ao).collect::<Vec<_>>()
+        };
+        // sorted by position, (0, 0), (0, 1), (1, 0), (1, 1)
+        assert_eq!(ao_of_top(&world), [3, 3, 3, 3]);
+
+        // a block diagonally above the (1, 1) corner only occludes it a little
+        world.set_block(Vec3::new(1, 1, 1), Block::STONE);
+        assert_eq!(ao_of_top(&world), [3, 3, 3, 2]);
+
+        // blocks on both sides of a corner occlude it completely
+        world.set_block(Vec3::new(1, 1, 0), Block::STONE);
+        world.set_block(Vec3::new(0, 1, 1), Block::STONE);
+        assert_eq!(ao_of_top(&world), [3, 2, 2, 0]);
+    }
+
+    #[test]
+    fn quads_are_split_along_the_brighter_diagonal() {
+        let mut world = World::empty(0);
+        world.set_block(Vec3::new(0, 0, 0), Block::STONE);
+        world.set_block(Vec3::new(1, 1, 1), Block::STONE);
+        let mesh = mesh_opaque(&world, Vec3::all(0), &Settings::default());
+        let face = (mesh.indices.chunks(6))
+            .find(|quad| mesh.vertices[quad[0] as usize].unpack().face == Face::PosY)
+            .unwrap();
+
+        // the darker corner isn't on the diagonal shared by both triangles
+        let darker = (face.iter())
+            .find(|&&i| mesh.vertices[i as usize].unpack().ao < 3)
+            .unwrap();
+        assert_eq!(face.iter().filter(|&i| i == darker).count(), 1);
+    }
+
+    #[test]
+    fn flat_lighting_uses_the_block_in_front() {
+        let mut world = World::empty(0);
+        world.set_block(Vec3::new(0, 0, 0), Block::STONE);
+        world.set_block(Vec3::new(0, 2, 0), Block::LAMP);
+        let settings = Settings {
+            smooth_lighting: false,
+            ..Default::default()
+        };
+        // the lamp is right above the block in front of the face, and blocks the sky
+        for v in top_vertices(&world, 1, &settings) {
+            assert_eq!((v.sky_light, v.block_light), (14, 14), "{v:?}");
+        }
+    }
+
+    #[test]
+    fn smooth_lighting_averages_the_blocks_around_corners() {
+        let mut world = World::empty(0);
+        world.set_block(Vec3::new(4, 4, 4), Block::STONE);
+        world.set_block(Vec3::new(5, 4, 4), Block::STONE);
+        world.set_block(Vec3::new(4, 6, 4), Block::LAMP);
+        world.set_block(Vec3::new(6, 5, 5), Block::STONE);
+        let block_light: Vec<_> = (top_vertices(&world, 5, &Settings::default()).iter())
+            .map(|v| (v.pos.x, v.pos.z, v.block_light))
+            .collect();
+
+        // the block light above the top faces is 14 right below the lamp, and 1 less per block
+        // further away, so the corners around it average out to 13
+        assert_eq!(
+            block_light,
+            [
+                (4, 4, 13),
+                (4, 5, 13),
+                (5, 4, 13),
+                (5, 4, 13),
+                (5, 5, 13),
+                (5, 5, 13),
+                // (12 + 11 + 13 + 12) / 4
+                (6, 4, 12),
+                // (13 + 12 + 12) / 3, the stone at (6, 5, 5) is left out
+                (6, 5, 12),
+            ]
+        );
+    }
+}
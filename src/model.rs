@@ -1,22 +1,26 @@
-use crate::Vec3;
+use crate::block::{Block, ATLAS_SIZE};
+use crate::math::Vec3;
+use wgpu::util::DeviceExt;
 
 #[repr(C)]
 #[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Vertex {
     pos: [f32; 3],
     tex_coords: [f32; 2],
+    normal: [f32; 3],
 }
 impl Vertex {
     #[inline(always)]
-    pub const fn new(pos: [f32; 3], tex_coords: [f32; 2]) -> Self {
+    pub const fn new(pos: [f32; 3], tex_coords: [f32; 2], normal: [f32; 3]) -> Self {
         Self {
-            pos: pos,
-            tex_coords: tex_coords,
+            pos,
+            tex_coords,
+            normal,
         }
     }
 
-    pub const ATTRIBS: [wgpu::VertexAttribute; 2] =
-        wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x2];
+    pub const ATTRIBS: [wgpu::VertexAttribute; 3] =
+        wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x2, 2 => Float32x3];
 
     pub fn buffer_layout() -> wgpu::VertexBufferLayout<'static> {
         wgpu::VertexBufferLayout {
@@ -27,38 +31,134 @@ impl Vertex {
     }
 }
 
+/// The indices of the two triangles making up a quad from one of the `*_face_verts` helpers.
+/// Both triangles keep the helpers' counter-clockwise winding.
+pub const QUAD_INDICES: [u16; 6] = [0, 1, 2, 3, 0, 2];
+/// Like `QUAD_INDICES`, but split along the other diagonal (from vertex 1 to 3, instead of 0 to 2).
+pub const QUAD_INDICES_FLIPPED: [u16; 6] = [1, 2, 3, 1, 3, 0];
+
 pub const NUM_INDICES: u32 = 6 * 6;
 pub fn default_model() -> (Vec<Vertex>, Vec<u16>) {
     let (from, to) = (Vec3::all(0.0), Vec3::all(1.0));
     let mut vertices = vec![];
     let mut indices = vec![];
     let mut next_index = 0;
-    let mut quad = |verts: [Vec3<f32>; 4]| {
-        vertices.push(Vertex::new(verts[0].into(), [1.0, 0.0]));
-        vertices.push(Vertex::new(verts[1].into(), [0.0, 0.0]));
-        vertices.push(Vertex::new(verts[2].into(), [0.0, 1.0]));
-        vertices.push(Vertex::new(verts[3].into(), [1.0, 1.0]));
-        indices.extend([
-            next_index + 0,
-            next_index + 1,
-            next_index + 2,
-            next_index + 3,
-            next_index + 0,
-            next_index + 2,
-        ]);
+    for face in Face::ALL {
+        let normal = face.normal().as_f32().into();
+        for (vert, tex_coords) in face.verts(from, to).into_iter().zip(FACE_TEX_COORDS) {
+            vertices.push(Vertex::new(vert.into(), tex_coords, normal));
+        }
+        indices.extend(QUAD_INDICES.map(|i| next_index + i));
         next_index += 4;
-    };
-
-    quad(px_face_verts(from, to));
-    quad(nx_face_verts(from, to));
-    quad(pz_face_verts(from, to));
-    quad(nz_face_verts(from, to));
-    quad(py_face_verts(from, to));
-    quad(ny_face_verts(from, to));
+    }
 
     (vertices, indices)
 }
 
+/// `default_model` with the center of its bottom at the origin, like entity models are, with each
+/// face textured like that face of `block`, from the block atlas.
+pub fn block_model(block: Block) -> Vec<Vertex> {
+    let (vertices, _) = default_model();
+    let textures = block.info().textures;
+    let offset = Vec3::new(-0.5, 0.0, -0.5);
+    (vertices.into_iter().enumerate())
+        .map(|(i, vertex)| {
+            // `default_model` has four vertices per face, in the order of `Face::ALL`
+            let texture = textures.get(Face::from_index(i as u32 / 4)) as u32;
+            let tile = [texture % ATLAS_SIZE, texture / ATLAS_SIZE];
+            let tex_coords =
+                [0, 1].map(|e| (tile[e] as f32 + vertex.tex_coords[e]) / ATLAS_SIZE as f32);
+            let pos = Vec3::from(vertex.pos) + offset;
+            Vertex::new(pos.into(), tex_coords, vertex.normal)
+        })
+        .collect()
+}
+
+/// A model uploaded to the GPU.
+pub struct ModelMesh {
+    pub vertex_buffer: wgpu::Buffer,
+    pub index_buffer: wgpu::Buffer,
+    pub num_indices: u32,
+}
+impl ModelMesh {
+    pub fn new(device: &wgpu::Device, label: &str, vertices: &[Vertex], indices: &[u16]) -> Self {
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&format!("{label} vertex buffer")),
+            contents: bytemuck::cast_slice(vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&format!("{label} index buffer")),
+            contents: bytemuck::cast_slice(indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+        Self {
+            vertex_buffer,
+            index_buffer,
+            num_indices: indices.len() as u32,
+        }
+    }
+}
+
+/// One of the six faces of a box, named by the axis its normal points along.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Face {
+    PosX = 0,
+    NegX = 1,
+    PosY = 2,
+    NegY = 3,
+    PosZ = 4,
+    NegZ = 5,
+}
+impl Face {
+    pub const ALL: [Face; 6] = [
+        Face::PosX,
+        Face::NegX,
+        Face::PosY,
+        Face::NegY,
+        Face::PosZ,
+        Face::NegZ,
+    ];
+
+    #[inline(always)]
+    pub const fn from_index(index: u32) -> Self {
+        Self::ALL[index as usize]
+    }
+
+    #[inline(always)]
+    pub const fn normal(self) -> Vec3<i32> {
+        match self {
+            Face::PosX => Vec3::new(1, 0, 0),
+            Face::NegX => Vec3::new(-1, 0, 0),
+            Face::PosY => Vec3::new(0, 1, 0),
+            Face::NegY => Vec3::new(0, -1, 0),
+            Face::PosZ => Vec3::new(0, 0, 1),
+            Face::NegZ => Vec3::new(0, 0, -1),
+        }
+    }
+
+    /// The corners of this face of the box `from..to`, see the `*_face_verts` helpers.
+    #[inline(always)]
+    pub fn verts<T: Copy>(self, from: Vec3<T>, to: Vec3<T>) -> [Vec3<T>; 4] {
+        match self {
+            Face::PosX => px_face_verts(from, to),
+            Face::NegX => nx_face_verts(from, to),
+            Face::PosY => py_face_verts(from, to),
+            Face::NegY => ny_face_verts(from, to),
+            Face::PosZ => pz_face_verts(from, to),
+            Face::NegZ => nz_face_verts(from, to),
+        }
+    }
+}
+
+/// The texture coordinates of each corner returned by the `*_face_verts` helpers.
+/// The first two corners are along the top edge of side faces, so textures are upright.
+pub const FACE_TEX_COORDS: [[f32; 2]; 4] = [[1.0, 0.0], [0.0, 0.0], [0.0, 1.0], [1.0, 1.0]];
+
+// The `*_face_verts` helpers return the corners of one face of the box `from..to`,
+// in counter-clockwise order when looking at the face from outside the box.
+// This is the front face for `FrontFace::Ccw`, so back-face culling hides the inside faces.
+
 pub fn px_face_verts<T: Copy>(from: Vec3<T>, to: Vec3<T>) -> [Vec3<T>; 4] {
     [
         Vec3::new(to.x, to.y, from.z),   // X1 Y1 Z0
@@ -107,3 +207,49 @@ pub fn ny_face_verts<T: Copy>(from: Vec3<T>, to: Vec3<T>) -> [Vec3<T>; 4] {
         Vec3::new(from.x, from.y, to.z),   // X0 Y0 Z1
     ]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn face_normal(verts: [Vec3<f32>; 4], tri: &[u16]) -> Vec3<f32> {
+        let [a, b, c] = [0, 1, 2].map(|i| verts[tri[i] as usize]);
+        (b - a).cross(c - a).norm()
+    }
+
+    #[test]
+    fn faces_wind_counter_clockwise_from_outside() {
+        let (from, to) = (Vec3::new(-1.0, 2.0, 0.5), Vec3::new(3.0, 2.5, 4.0));
+        let center = (from + to) * 0.5;
+        for face in Face::ALL {
+            let (verts, expected) = (face.verts(from, to), face.normal().as_f32());
+            let face_center = (verts[0] + verts[1] + verts[2] + verts[3]) * 0.25;
+            // either way of splitting the quad has to keep the winding
+            for tri in QUAD_INDICES.chunks(3).chain(QUAD_INDICES_FLIPPED.chunks(3)) {
+                let normal = face_normal(verts, tri);
+                assert!(
+                    (normal - expected).len() < 1e-5,
+                    "{normal:?} != {expected:?}"
+                );
+                assert!(normal.dot(face_center - center) > 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn default_model_faces_point_outwards() {
+        let (vertices, indices) = default_model();
+        assert_eq!(indices.len(), NUM_INDICES as usize);
+
+        let center = Vec3::all(0.5);
+        for tri in indices.chunks(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| Vec3::from(vertices[tri[i] as usize].pos));
+            let normal = (b - a).cross(c - a);
+            let tri_center = (a + b + c) * (1.0 / 3.0);
+            assert!(
+                normal.dot(tri_center - center) > 0.0,
+                "{tri:?} faces inwards"
+            );
+        }
+    }
+}
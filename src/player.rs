@@ -1,16 +1,24 @@
 use crate::cam::Cam;
+use crate::collision::Aabb;
 use crate::input::{FrameInput, Key};
 use crate::math::{Vec2, Vec3};
 use std::ops::Neg;
 
 #[derive(Clone)]
 pub struct Player {
+    // the position of their eyes
     pub pos: Vec3<f32>,
+    // how far they're moving this tick. Set from the input, then blocks are collided with
     pub vel: Vec3<f32>,
     pub acc: Vec3<f32>,
     pub cam: Cam,
 }
 impl Player {
+    /// How far above the bottom of the player their eyes (the camera) are.
+    pub const EYE_HEIGHT: f32 = 1.6;
+    /// The size of the player's bounding box.
+    pub const SIZE: Vec3<f32> = Vec3::new(0.6, 1.8, 0.6);
+
     pub fn new(pos: Vec3<f32>, rot: Vec3<f32>) -> Self {
         Self {
             pos,
@@ -23,6 +31,12 @@ impl Player {
         }
     }
 
+    #[inline(always)]
+    pub fn aabb(&self) -> Aabb {
+        let feet = self.pos - Vec3::new(0.0, Self::EYE_HEIGHT, 0.0);
+        Aabb::from_bottom_center(feet, Self::SIZE)
+    }
+
     pub fn move_cursor(&mut self, delta: Vec2<f64>) {
         const SENSITIVITY: f32 = 0.4;
 
@@ -37,6 +51,7 @@ impl Player {
         // the camera does not rotate about the Z axis. That would be like tilting your head
     }
 
+    /// Turns the camera, and sets how far the player moves this tick from the keys held.
     pub fn update(&mut self, input: &FrameInput) {
         self.move_cursor(input.cursor_change().map(f64::neg));
 
@@ -45,29 +60,29 @@ impl Player {
         let dz = SPEED * self.cam.rot.y.to_radians().cos();
         let dy = SPEED;
 
+        // the world moves the player by `vel`, so they don't fly through blocks
+        self.vel = Vec3::all(0.0);
         if input.key_pressed(Key::W) {
-            self.pos.x -= dx;
-            self.pos.z -= dz;
+            self.vel.x -= dx;
+            self.vel.z -= dz;
         }
         if input.key_pressed(Key::S) {
-            self.pos.x += dx;
-            self.pos.z += dz;
+            self.vel.x += dx;
+            self.vel.z += dz;
         }
         if input.key_pressed(Key::D) {
-            self.pos.x += dz;
-            self.pos.z -= dx;
+            self.vel.x += dz;
+            self.vel.z -= dx;
         }
         if input.key_pressed(Key::A) {
-            self.pos.x -= dz;
-            self.pos.z += dx;
+            self.vel.x -= dz;
+            self.vel.z += dx;
         }
         if input.key_pressed(Key::Space) {
-            self.pos.y += dy;
+            self.vel.y += dy;
         }
         if input.key_pressed(Key::LShift) {
-            self.pos.y -= dy;
+            self.vel.y -= dy;
         }
-
-        self.cam.pos = self.pos;
     }
 }
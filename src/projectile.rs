@@ -0,0 +1,67 @@
+//! Blocks thrown through the air, which drop as an item where they hit something.
+
+use crate::block::Block;
+use crate::entity::{Entity, EntityKind, EntityModel};
+use crate::item;
+use crate::math::Vec3;
+use crate::world::World;
+
+/// The size of a projectile.
+pub const SIZE: f32 = 0.25;
+/// How fast a projectile is thrown, in blocks per tick.
+pub const SPEED: f32 = 1.0;
+/// How fast projectiles spin about the Y axis while flying, in degrees per tick.
+/// Models turn about the center of their bottom, so any other axis would make them wobble.
+pub const SPIN_SPEED: f32 = 20.0;
+
+/// A projectile of `block` thrown from `pos` (the center of its bottom) towards `dir`.
+pub fn thrown(block: Block, pos: Vec3<f32>, dir: Vec3<f32>) -> Entity {
+    let kind = EntityKind::Projectile(block);
+    let mut entity = Entity::new(kind, EntityModel::Block(block), pos, Vec3::all(SIZE));
+    entity.vel = dir.norm() * SPEED;
+    entity
+}
+
+/// Spins the projectile, or drops its `block` as an item once it hits a block.
+/// Returns whether it's still flying.
+pub fn update_entity(world: &mut World, entity: &mut Entity, block: Block) -> bool {
+    if entity.collided {
+        world.entities.push(item::entity(block, entity.pos));
+        return false;
+    }
+    entity.turn(Vec3::new(0.0, 1.0, 0.0), SPIN_SPEED);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::EntityKind;
+
+    #[test]
+    fn projectiles_drop_as_items_where_they_hit() {
+        let mut world = World::with_floor(4);
+        for y in 1..=4 {
+            for z in -4..=4 {
+                world.set_block(Vec3::new(3, y, z), Block::STONE);
+            }
+        }
+        let dir = Vec3::new(1.0, 0.1, 0.0);
+        world
+            .entities
+            .push(thrown(Block::GRAVEL, Vec3::new(0.5, 2.0, 0.5), dir));
+        world.tick_n(2);
+        assert_eq!(world.entities.len(), 1);
+        assert_eq!(
+            world.entities[0].kind,
+            EntityKind::Projectile(Block::GRAVEL)
+        );
+
+        world.tick_n(60);
+        assert_eq!(world.entities.len(), 1);
+        let item = &world.entities[0];
+        assert_eq!(item.kind, EntityKind::Item(Block::GRAVEL));
+        assert!(item.on_ground);
+        assert!(item.aabb().max.x <= 3.0, "{:?}", item.aabb());
+    }
+}
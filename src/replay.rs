@@ -0,0 +1,141 @@
+use crate::input::{FrameInput, Key};
+use crate::math::Vec2;
+use crate::world::World;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use winit::event::MouseButton;
+
+/// Bumped whenever the recording format, or the way the world reacts to input, changes
+/// in a way that would make old recordings replay differently. That includes where the
+/// player spawns and the terrain generated from the seed, not just player movement.
+pub const RECORDING_VERSION: u32 = 7;
+
+/// The input for a single tick, in a form that can be written to a file.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TickInput {
+    pub pressed_keys: Vec<Key>,
+    pub pressed_mouse_buttons: Vec<MouseButton>,
+
+    pub just_pressed_keys: Vec<Key>,
+    pub just_released_keys: Vec<Key>,
+    pub repeated_keys: Vec<Key>,
+    pub just_pressed_mouse_buttons: Vec<MouseButton>,
+    pub just_released_mouse_buttons: Vec<MouseButton>,
+
+    pub cursor_change: [f64; 2],
+    pub scroll_change: [f64; 2],
+    pub text_input: String,
+}
+impl TickInput {
+    pub fn to_frame_input(&self) -> FrameInput {
+        FrameInput {
+            pressed_keys: self.pressed_keys.iter().copied().collect(),
+            pressed_mouse_buttons: self.pressed_mouse_buttons.iter().copied().collect(),
+
+            just_pressed_keys: self.just_pressed_keys.iter().copied().collect(),
+            just_released_keys: self.just_released_keys.iter().copied().collect(),
+            repeated_keys: self.repeated_keys.iter().copied().collect(),
+            just_pressed_mouse_buttons: self.just_pressed_mouse_buttons.iter().copied().collect(),
+            just_released_mouse_buttons: self.just_released_mouse_buttons.iter().copied().collect(),
+
+            cursor_change: Vec2::from(self.cursor_change),
+            scroll_change: Vec2::from(self.scroll_change),
+            text_input: self.text_input.clone(),
+        }
+    }
+}
+impl From<&FrameInput> for TickInput {
+    fn from(input: &FrameInput) -> Self {
+        // sets have no stable order, so they're sorted to make the files reproducible
+        fn keys<'a>(set: impl IntoIterator<Item = &'a Key>) -> Vec<Key> {
+            let mut keys: Vec<Key> = set.into_iter().copied().collect();
+            keys.sort();
+            keys
+        }
+        fn buttons<'a>(set: impl IntoIterator<Item = &'a MouseButton>) -> Vec<MouseButton> {
+            let mut buttons: Vec<MouseButton> = set.into_iter().copied().collect();
+            buttons.sort_by_key(|button| match button {
+                MouseButton::Left => (0, 0),
+                MouseButton::Right => (1, 0),
+                MouseButton::Middle => (2, 0),
+                MouseButton::Other(n) => (3, *n),
+            });
+            buttons
+        }
+        Self {
+            pressed_keys: keys(&input.pressed_keys),
+            pressed_mouse_buttons: buttons(&input.pressed_mouse_buttons),
+
+            just_pressed_keys: keys(&input.just_pressed_keys),
+            just_released_keys: keys(&input.just_released_keys),
+            repeated_keys: keys(&input.repeated_keys),
+            just_pressed_mouse_buttons: buttons(&input.just_pressed_mouse_buttons),
+            just_released_mouse_buttons: buttons(&input.just_released_mouse_buttons),
+
+            cursor_change: input.cursor_change.pod(),
+            scroll_change: input.scroll_change.pod(),
+            text_input: input.text_input.clone(),
+        }
+    }
+}
+
+/// A recorded session: the seed the world was created with, and the input for every tick.
+/// Replaying it on a fresh `World` reproduces the session exactly.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Recording {
+    pub version: u32,
+    pub seed: u64,
+    pub ticks: Vec<TickInput>,
+}
+impl Recording {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            version: RECORDING_VERSION,
+            seed,
+            ticks: Vec::new(),
+        }
+    }
+
+    #[inline(always)]
+    pub fn record(&mut self, input: &FrameInput) {
+        self.ticks.push(TickInput::from(input));
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        bincode::serialize_into(file, self).map_err(to_io_error)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let file = std::io::BufReader::new(std::fs::File::open(path)?);
+        let recording: Self = bincode::deserialize_from(file).map_err(to_io_error)?;
+        if recording.version != RECORDING_VERSION {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "recording is version {}, expected version {RECORDING_VERSION}",
+                    recording.version
+                ),
+            ));
+        }
+        Ok(recording)
+    }
+
+    /// Creates a world from the recorded seed, and runs every recorded tick on it.
+    pub fn replay(&self) -> World {
+        let mut world = World::new(self.seed);
+        self.replay_on(&mut world);
+        world
+    }
+
+    /// Runs every recorded tick on `world`.
+    pub fn replay_on(&self, world: &mut World) {
+        for tick in &self.ticks {
+            world.tick(&tick.to_frame_input());
+        }
+    }
+}
+
+fn to_io_error(err: bincode::Error) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, err)
+}
@@ -0,0 +1,27 @@
+use crate::world::WORLD_RADIUS;
+
+/// Options the player can change, that affect how the game is rendered.
+#[derive(Clone, Debug)]
+pub struct Settings {
+    // horizontal field of view, in degrees
+    pub fov: f32,
+    // map depth so that near is 1 and far is 0, with an infinite far plane.
+    // much better depth precision at a distance than the regular mapping
+    pub reverse_z: bool,
+    // light each corner of a block face by the blocks around it, instead of lighting the whole
+    // face by the block in front of it, so light fades smoothly instead of in steps
+    pub smooth_lighting: bool,
+    // how many chunks away from the camera are drawn, in each horizontal direction.
+    // Fog thickens towards this distance, so chunks fade out instead of popping
+    pub render_distance: u32,
+}
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            fov: 70.0,
+            reverse_z: true,
+            smooth_lighting: true,
+            render_distance: WORLD_RADIUS as u32,
+        }
+    }
+}
@@ -0,0 +1,303 @@
+//! The day/night cycle: how the time of day changes the sky and the lighting.
+
+use crate::block::Block;
+use crate::math::{Mat4, Vec3};
+use crate::texture::{Texture, DEPTH_TEXTURE_FORMAT};
+use crate::world::TICKS_PER_SECOND;
+use crate::{Fog, Lighting, Uniform};
+
+/// How many ticks a full day and night takes.
+pub const DAY_LENGTH: u32 = 20 * 60 * TICKS_PER_SECOND;
+
+pub const SUNRISE: u32 = 0;
+pub const NOON: u32 = DAY_LENGTH / 4;
+pub const SUNSET: u32 = DAY_LENGTH / 2;
+pub const MIDNIGHT: u32 = DAY_LENGTH * 3 / 4;
+/// The time new worlds start at, in the morning.
+pub const DAY_START: u32 = DAY_LENGTH / 12;
+
+const DAY_ZENITH: Vec3<f32> = Vec3::new(0.22, 0.45, 0.9);
+const DAY_HORIZON: Vec3<f32> = Vec3::new(0.6, 0.75, 0.95);
+const NIGHT_ZENITH: Vec3<f32> = Vec3::new(0.005, 0.007, 0.02);
+const NIGHT_HORIZON: Vec3<f32> = Vec3::new(0.02, 0.025, 0.06);
+// added to the horizon around sunrise and sunset
+const SUNSET_GLOW: Vec3<f32> = Vec3::new(0.55, 0.22, 0.05);
+
+const WATER_FOG: Vec3<f32> = Vec3::new(0.05, 0.2, 0.45);
+const LAVA_FOG: Vec3<f32> = Vec3::new(0.8, 0.25, 0.02);
+
+const SUN_COLOR: Vec3<f32> = Vec3::new(1.0, 1.0, 0.95);
+const SUNSET_SUN_COLOR: Vec3<f32> = Vec3::new(1.0, 0.6, 0.35);
+const MOON_COLOR: Vec3<f32> = Vec3::new(0.3, 0.35, 0.5);
+
+/// How far through the day `time` is, as an angle in radians. 0 is sunrise, PI is sunset.
+#[inline(always)]
+pub fn sun_angle(time: u32) -> f32 {
+    (time % DAY_LENGTH) as f32 / DAY_LENGTH as f32 * std::f32::consts::TAU
+}
+
+/// The normalized direction pointing towards the sun.
+/// The sun rises in +X and sets in -X, and is tilted a little towards +Z,
+/// so that it's never exactly overhead and every side of a block is lit differently.
+pub fn sun_dir(time: u32) -> Vec3<f32> {
+    let angle = sun_angle(time);
+    Vec3::new(angle.cos(), angle.sin(), 0.3).norm()
+}
+
+/// The normalized direction pointing towards the moon, always opposite the sun.
+pub fn moon_dir(time: u32) -> Vec3<f32> {
+    Vec3::all(0.0) - sun_dir(time)
+}
+
+/// How much like day (1) or night (0) it is. Changes smoothly while the sun is near the horizon.
+pub fn daylight(time: u32) -> f32 {
+    let t = ((sun_dir(time).y + 0.1) * 4.0).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// How close the sun is to the horizon, 1 when it is right on it, fading to 0 away from it.
+fn sunset(time: u32) -> f32 {
+    (1.0 - sun_dir(time).y.abs() * 4.0).clamp(0.0, 1.0)
+}
+
+/// The color of the sky straight up, and at the horizon.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SkyColors {
+    pub zenith: Vec3<f32>,
+    pub horizon: Vec3<f32>,
+}
+
+pub fn sky_colors(time: u32) -> SkyColors {
+    let daylight = daylight(time);
+    SkyColors {
+        zenith: NIGHT_ZENITH.lerp(DAY_ZENITH, daylight),
+        horizon: NIGHT_HORIZON.lerp(DAY_HORIZON, daylight) + SUNSET_GLOW * sunset(time),
+    }
+}
+
+/// The lighting at `time`. The sun lights the world during the day, and the moon at night.
+pub fn lighting(time: u32) -> Lighting {
+    let daylight = daylight(time);
+    let (dir, color) = if sun_dir(time).y > 0.0 {
+        let color = SUN_COLOR.lerp(SUNSET_SUN_COLOR, sunset(time));
+        (sun_dir(time), color)
+    } else {
+        (moon_dir(time), MOON_COLOR)
+    };
+    // sky light fades at night, but never all the way, so the world is still visible
+    let sky_light = 0.2 + 0.8 * daylight;
+    let ambient = 0.25 + 0.2 * daylight;
+    Lighting::new(dir, color.into(), ambient, sky_light)
+}
+
+/// The fog at `time`, for a camera that can see `view_distance` blocks away.
+/// Terrain fades into the horizon, so the edge of the world blends into the sky.
+/// If the camera is inside `liquid`, the fog is much thicker and the color of the liquid.
+pub fn fog(time: u32, view_distance: f32, liquid: Option<Block>) -> Fog {
+    match liquid {
+        // lava glows, so it's just as bright at night
+        Some(Block::LAVA) => Fog::exponential(LAVA_FOG.into(), 0.6),
+        Some(_) => Fog::exponential((WATER_FOG * (0.2 + 0.8 * daylight(time))).into(), 0.08),
+        None => Fog::linear(
+            sky_colors(time).horizon.into(),
+            view_distance * 0.6,
+            view_distance,
+        ),
+    }
+}
+
+/// How big the sun and moon look, as the tangent of half the angle they cover.
+const SUN_SIZE: f32 = 0.1;
+const MOON_SIZE: f32 = 0.08;
+
+/// Everything `sky.wgsl` needs to draw the sky, laid out to match `Sky` in it.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct SkyUniform {
+    pub inv_view_proj: Mat4,
+    pub zenith: [f32; 3],
+    pub star_visibility: f32,
+    pub horizon: [f32; 3],
+    pub star_rotation: f32,
+    pub sun_dir: [f32; 3],
+    pub sun_size: f32,
+    pub moon_dir: [f32; 3],
+    pub moon_size: f32,
+}
+impl SkyUniform {
+    pub fn new(time: u32, view: Mat4, proj: Mat4) -> Self {
+        // the sky is infinitely far away, so moving the camera doesn't move it
+        let mut view_rotation = view;
+        view_rotation.0[3] = [0.0, 0.0, 0.0, 1.0];
+        let inv_view_proj = (proj * view_rotation).inverse().unwrap_or(Mat4::identity());
+
+        let colors = sky_colors(time);
+        let night = 1.0 - daylight(time);
+        Self {
+            inv_view_proj,
+            zenith: colors.zenith.into(),
+            star_visibility: night * night,
+            horizon: colors.horizon.into(),
+            star_rotation: sun_angle(time),
+            sun_dir: sun_dir(time).into(),
+            sun_size: SUN_SIZE,
+            moon_dir: moon_dir(time).into(),
+            moon_size: MOON_SIZE,
+        }
+    }
+}
+
+/// Draws the sky, before anything else.
+pub struct SkyRenderer {
+    pipeline: wgpu::RenderPipeline,
+    texture_bind_group: wgpu::BindGroup,
+    uniform_bind_group: wgpu::BindGroup,
+    pub uniform: Uniform<SkyUniform>,
+}
+impl SkyRenderer {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        format: wgpu::TextureFormat,
+        uniform: SkyUniform,
+    ) -> Self {
+        let load = |bytes: &[u8], label| {
+            let image = image::load_from_memory(bytes).unwrap();
+            Texture::from_image(
+                device,
+                queue,
+                &image,
+                wgpu::FilterMode::Nearest,
+                Some(label),
+            )
+        };
+        let sun = load(include_bytes!("../res/sun.png"), "sun");
+        let moon = load(include_bytes!("../res/moon.png"), "moon");
+
+        let texture_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("sky_texture_bind_group_layout"),
+                entries: &[
+                    sun.bind_layout(0),
+                    moon.bind_layout(1),
+                    sun.sampler_bind_layout(2),
+                ],
+            });
+        let texture_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("sky_texture_bind_group"),
+            layout: &texture_bind_group_layout,
+            entries: &[sun.binding(0), moon.binding(1), sun.sampler_binding(2)],
+        });
+
+        let uniform = Uniform::new(device, uniform);
+        let uniform_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("sky_uniform_bind_group_layout"),
+                entries: &[uniform
+                    .bind_layout(0, wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT)],
+            });
+        let uniform_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("sky_uniform_bind_group"),
+            layout: &uniform_bind_group_layout,
+            entries: &[uniform.binding(0)],
+        });
+
+        let shader = device.create_shader_module(wgpu::include_wgsl!("../res/sky.wgsl"));
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("sky_pipeline"),
+            bind_group_layouts: &[&texture_bind_group_layout, &uniform_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("sky_pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                // the vertices are generated in the shader
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                cull_mode: None,
+                ..Default::default()
+            },
+            // the sky is drawn first, behind everything. It leaves the depth buffer as it was
+            // cleared, so terrain drawn after it is unaffected
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_TEXTURE_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            pipeline,
+            texture_bind_group,
+            uniform_bind_group,
+            uniform,
+        }
+    }
+
+    pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.texture_bind_group, &[]);
+        render_pass.set_bind_group(1, &self.uniform_bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sun_is_up_during_the_day() {
+        assert!(sun_dir(NOON).y > 0.9);
+        assert!(sun_dir(MIDNIGHT).y < -0.9);
+        assert!(sun_dir(SUNRISE).y.abs() < 1e-4);
+        assert!(sun_dir(SUNRISE).x > 0.9);
+        assert!(sun_dir(SUNSET).x < -0.9);
+        // time wraps around every day
+        assert_eq!(sun_dir(NOON), sun_dir(NOON + DAY_LENGTH * 3));
+    }
+
+    #[test]
+    fn fog_hides_the_edge_of_the_view() {
+        let air = fog(NOON, 64.0, None);
+        assert_eq!(air.amount(10.0), 0.0);
+        assert_eq!(air.amount(64.0), 1.0);
+        assert!(air.amount(50.0) > 0.0 && air.amount(50.0) < 1.0);
+        assert_eq!(air.color, <[f32; 3]>::from(sky_colors(NOON).horizon));
+
+        // underwater, even nearby blocks are hard to see
+        let water = fog(NOON, 64.0, Some(Block::WATER));
+        assert!(water.amount(20.0) > 0.9);
+        assert!(water.amount(20.0) > water.amount(10.0));
+    }
+
+    #[test]
+    fn daylight_fades_smoothly() {
+        assert_eq!(daylight(NOON), 1.0);
+        assert_eq!(daylight(MIDNIGHT), 0.0);
+        let mut prev = daylight(NOON);
+        for time in NOON..MIDNIGHT {
+            let daylight = daylight(time);
+            assert!(daylight <= prev && prev - daylight < 0.01, "at {time}");
+            prev = daylight;
+        }
+    }
+}
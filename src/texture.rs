@@ -6,13 +6,14 @@ pub struct Texture {
     pub sampler: wgpu::Sampler,
 }
 
-pub const DEPTH_TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth16Unorm;
+pub const DEPTH_TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
 
 impl Texture {
     pub fn from_image(
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         img: &image::DynamicImage,
+        mag_filter: wgpu::FilterMode,
         label: Option<&str>,
     ) -> Self {
         let rgba = img.to_rgba8();
@@ -54,7 +55,7 @@ impl Texture {
             address_mode_u: wgpu::AddressMode::ClampToEdge,
             address_mode_v: wgpu::AddressMode::ClampToEdge,
             address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Linear,
+            mag_filter,
             min_filter: wgpu::FilterMode::Nearest,
             mipmap_filter: wgpu::FilterMode::Nearest,
             ..Default::default()
@@ -123,13 +124,13 @@ impl Texture {
         }
     }
 
-    pub fn binding(&self, binding: u32) -> wgpu::BindGroupEntry {
+    pub fn binding(&self, binding: u32) -> wgpu::BindGroupEntry<'_> {
         wgpu::BindGroupEntry {
             binding,
             resource: wgpu::BindingResource::TextureView(&self.view),
         }
     }
-    pub fn sampler_binding(&self, binding: u32) -> wgpu::BindGroupEntry {
+    pub fn sampler_binding(&self, binding: u32) -> wgpu::BindGroupEntry<'_> {
         wgpu::BindGroupEntry {
             binding,
             resource: wgpu::BindingResource::Sampler(&self.sampler),
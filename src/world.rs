@@ -0,0 +1,289 @@
+use crate::block::Block;
+use crate::block_update::{self, BlockUpdates, MAX_UPDATES_PER_TICK};
+use crate::chunk::{chunk_origin, chunk_pos, local_pos, Chunk, CHUNK_SIZE};
+use crate::collision;
+use crate::console::{self, Console};
+use crate::entity::{self, Entity};
+use crate::falling_block;
+use crate::fluid;
+use crate::grass;
+use crate::input::FrameInput;
+use crate::light::{self, LightKind};
+use crate::math::Vec3;
+use crate::model::Face;
+use crate::player::Player;
+use crate::sky;
+use crate::worldgen;
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, HashSet};
+use std::ops::Bound::{Excluded, Unbounded};
+
+/// How many times per second the world is updated.
+/// Game logic is written in terms of ticks, not frames, so it behaves
+/// the same (and replays the same) no matter the frame rate.
+pub const TICKS_PER_SECOND: u32 = 60;
+pub const TICK_DURATION: std::time::Duration =
+    std::time::Duration::from_nanos(1_000_000_000 / TICKS_PER_SECOND as u64);
+
+/// How many chunks are generated in each horizontal direction from the origin.
+pub const WORLD_RADIUS: i32 = 4;
+/// The range of chunk Y positions that are generated.
+pub const WORLD_CHUNK_HEIGHT: std::ops::RangeInclusive<i32> = -2..=0;
+
+/// All of the game state that is simulated each tick.
+/// Doesn't know anything about the window or the GPU, so it can be driven headlessly.
+#[derive(Clone)]
+pub struct World {
+    pub seed: u64,
+    // the number of ticks that have passed since the world was created
+    pub tick: u64,
+    // the time of day, in ticks since sunrise, 0..`sky::DAY_LENGTH`
+    pub time: u32,
+    // while set, `time` doesn't advance
+    pub time_frozen: bool,
+    pub player: Player,
+    pub console: Console,
+
+    // sorted, so anything that visits every chunk does it in the same order every time
+    pub chunks: BTreeMap<Vec3<i32>, Chunk>,
+    // chunks with blocks that changed since the renderer last built their mesh
+    pub dirty_chunks: HashSet<Vec3<i32>>,
+    pub block_updates: BlockUpdates,
+    pub entities: Vec<Entity>,
+}
+impl World {
+    pub fn new(seed: u64) -> Self {
+        let mut chunks = BTreeMap::new();
+        for x in -WORLD_RADIUS..=WORLD_RADIUS {
+            for z in -WORLD_RADIUS..=WORLD_RADIUS {
+                for y in WORLD_CHUNK_HEIGHT {
+                    let pos = Vec3::new(x, y, z);
+                    chunks.insert(pos, worldgen::generate_chunk(seed, pos));
+                }
+            }
+        }
+        let chunk_positions: Vec<_> = chunks.keys().copied().collect();
+        let dirty_chunks = chunk_positions.iter().copied().collect();
+
+        // stand on the terrain, in the middle of the block at the origin
+        let spawn_height = worldgen::terrain_height(seed, 0, 0) as f32 + 1.0 + Player::EYE_HEIGHT;
+        let mut world = Self {
+            seed,
+            tick: 0,
+            time: sky::DAY_START,
+            time_frozen: false,
+            player: Player::new(Vec3::new(0.5, spawn_height, 0.5), Vec3::all(0.0)),
+            console: Console::default(),
+            chunks,
+            dirty_chunks,
+            block_updates: BlockUpdates::default(),
+            entities: Vec::new(),
+        };
+        light::light_chunks(&mut world, &chunk_positions);
+        world
+    }
+
+    /// An empty world, with no chunks. Useful for tests that build their own terrain.
+    pub fn empty(seed: u64) -> Self {
+        Self {
+            seed,
+            tick: 0,
+            time: sky::DAY_START,
+            time_frozen: false,
+            player: Player::new(Vec3::all(0.0), Vec3::all(0.0)),
+            console: Console::default(),
+            chunks: BTreeMap::new(),
+            dirty_chunks: HashSet::new(),
+            block_updates: BlockUpdates::default(),
+            entities: Vec::new(),
+        }
+    }
+
+    pub fn tick(&mut self, input: &FrameInput) {
+        let was_typing = self.console.is_open();
+        if let Some(line) = self.console.update(input) {
+            match console::run_command(self, &line) {
+                Ok(message) => log::info!("{message}"),
+                Err(err) => log::warn!("/{line}: {err}"),
+            }
+        }
+        // keys pressed while typing a command are for the command, not for moving around
+        if !was_typing && !self.console.is_open() {
+            self.player.update(input);
+        } else {
+            self.player.vel = Vec3::all(0.0);
+        }
+        self.move_player();
+        self.update_blocks();
+        self.random_ticks();
+        entity::update_all(self);
+
+        if !self.time_frozen {
+            self.time = (self.time + 1) % sky::DAY_LENGTH;
+        }
+        self.tick += 1;
+    }
+
+    /// Moves the player by their velocity, until they run into a block.
+    fn move_player(&mut self) {
+        let (moved, _) = collision::move_aabb(self, self.player.aabb(), self.player.vel);
+        self.player.pos = self.player.pos + moved;
+        self.player.cam.pos = self.player.pos;
+    }
+
+    /// Runs the block updates scheduled for this tick, up to `MAX_UPDATES_PER_TICK` of them.
+    pub fn update_blocks(&mut self) {
+        for pos in self.block_updates.take_due(self.tick, MAX_UPDATES_PER_TICK) {
+            let info = self.get_block(pos).info();
+            if info.liquid {
+                fluid::update(self, pos);
+            } else if info.falls {
+                falling_block::update(self, pos);
+            }
+        }
+    }
+
+    /// Gives a few random blocks in every chunk a random tick, see `block_update`.
+    pub fn random_ticks(&mut self) {
+        // the order matters when ticked blocks are next to each other. Each chunk is looked up
+        // after the last, since ticks may add chunks
+        let mut next = self.chunks.keys().next().copied();
+        while let Some(chunk_pos) = next {
+            for local in block_update::random_tick_positions(self.seed, self.tick, chunk_pos) {
+                let pos = chunk_origin(chunk_pos) + local;
+                if self.get_block(pos) == Block::GRASS {
+                    grass::random_tick(self, pos);
+                }
+            }
+            next = (self.chunks.range((Excluded(chunk_pos), Unbounded)))
+                .next()
+                .map(|(&pos, _)| pos);
+        }
+    }
+
+    /// Schedules an update of the block at `pos`, if it reacts to the blocks around it changing.
+    fn schedule_update(&mut self, pos: Vec3<i32>) {
+        let block = self.get_block(pos);
+        let delay = match block.info() {
+            info if info.liquid => fluid::update_delay(block),
+            info if info.falls => falling_block::FALL_DELAY,
+            _ => return,
+        };
+        self.block_updates.schedule(pos, self.tick + delay);
+    }
+
+    #[inline(always)]
+    pub fn chunk(&self, chunk_pos: Vec3<i32>) -> Option<&Chunk> {
+        self.chunks.get(&chunk_pos)
+    }
+
+    /// The block at `pos`. Blocks in chunks that aren't loaded are air.
+    pub fn get_block(&self, pos: Vec3<i32>) -> Block {
+        match self.chunks.get(&chunk_pos(pos)) {
+            Some(chunk) => chunk.get(local_pos(pos)),
+            None => Block::AIR,
+        }
+    }
+
+    /// Sets the block at `pos`, creating its chunk if it isn't loaded.
+    /// Fluids placed this way are sources.
+    pub fn set_block(&mut self, pos: Vec3<i32>, block: Block) {
+        self.set_fluid(pos, block, fluid::SOURCE);
+    }
+
+    /// The level of the fluid at `pos`, see `fluid`. 0 for other blocks.
+    pub fn fluid_level(&self, pos: Vec3<i32>) -> u8 {
+        match self.chunks.get(&chunk_pos(pos)) {
+            Some(chunk) => chunk.level(local_pos(pos)),
+            None => 0,
+        }
+    }
+
+    /// Sets the block at `pos` to `fluid`, at `level`, creating its chunk if it isn't loaded.
+    /// Schedules updates of the block and its neighbors, so they can react to the change.
+    pub fn set_fluid(&mut self, pos: Vec3<i32>, fluid: Block, level: u8) {
+        let chunk_pos = chunk_pos(pos);
+        if let Entry::Vacant(entry) = self.chunks.entry(chunk_pos) {
+            entry.insert(Chunk::new());
+            light::light_chunks(self, &[chunk_pos]);
+        }
+        let chunk = self.chunks.get_mut(&chunk_pos).unwrap();
+        let local = local_pos(pos);
+        let prev = chunk.get(local);
+        chunk.set(local, fluid);
+        chunk.set_level(local, level);
+        self.mark_dirty(pos);
+        if prev != fluid {
+            light::block_changed(self, pos);
+        }
+
+        self.schedule_update(pos);
+        for face in Face::ALL {
+            self.schedule_update(pos + face.normal());
+        }
+    }
+
+    /// The light level of `kind` at `pos`, or `None` if its chunk isn't loaded.
+    #[inline(always)]
+    pub fn light(&self, pos: Vec3<i32>, kind: LightKind) -> Option<u8> {
+        let chunk = self.chunks.get(&chunk_pos(pos))?;
+        Some(chunk.light(local_pos(pos), kind))
+    }
+
+    /// Sets the light level of `kind` at `pos`. Does nothing if its chunk isn't loaded.
+    pub fn set_light(&mut self, pos: Vec3<i32>, kind: LightKind, level: u8) {
+        let Some(chunk) = self.chunks.get_mut(&chunk_pos(pos)) else {
+            return;
+        };
+        let local = local_pos(pos);
+        if chunk.light(local, kind) != level {
+            chunk.set_light(local, kind, level);
+            self.mark_dirty(pos);
+        }
+    }
+
+    /// Marks the meshes of the chunk containing `pos` as out of date,
+    /// along with the meshes of any neighboring chunks that the block at `pos` touches.
+    fn mark_dirty(&mut self, pos: Vec3<i32>) {
+        let chunk_pos = chunk_pos(pos);
+        let local = local_pos(pos);
+        self.dirty_chunks.insert(chunk_pos);
+
+        let range = |l: i32| match l {
+            0 => -1..=0,
+            l if l == CHUNK_SIZE - 1 => 0..=1,
+            _ => 0..=0,
+        };
+        for x in range(local.x) {
+            for y in range(local.y) {
+                for z in range(local.z) {
+                    let neighbor = chunk_pos + Vec3::new(x, y, z);
+                    if self.chunks.contains_key(&neighbor) {
+                        self.dirty_chunks.insert(neighbor);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+impl World {
+    /// An empty world with a stone floor at y = 0, from -`radius` to `radius` on X and Z.
+    pub fn with_floor(radius: i32) -> Self {
+        let mut world = Self::empty(0);
+        for x in -radius..=radius {
+            for z in -radius..=radius {
+                world.set_block(Vec3::new(x, 0, z), Block::STONE);
+            }
+        }
+        world
+    }
+
+    /// Runs `ticks` ticks without any input.
+    pub fn tick_n(&mut self, ticks: u64) {
+        for _ in 0..ticks {
+            self.tick(&FrameInput::new());
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use crate::block::Block;
+use crate::chunk::{chunk_origin, Chunk, CHUNK_SIZE};
+use crate::math::Vec3;
+
+/// The terrain height that the hills are built around.
+pub const BASE_HEIGHT: i32 = -4;
+
+/// Hashes a seed and a 2D position, to a value in 0..1.
+fn hash(seed: u64, x: i32, z: i32) -> f32 {
+    // splitmix64 finalizer
+    let mut h = seed
+        ^ (x as u32 as u64).wrapping_mul(0x9E3779B97F4A7C15)
+        ^ (z as u32 as u64).wrapping_mul(0xC2B2AE3D27D4EB4F);
+    h = (h ^ (h >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    h = (h ^ (h >> 27)).wrapping_mul(0x94D049BB133111EB);
+    h ^= h >> 31;
+    (h >> 40) as f32 / (1u64 << 24) as f32
+}
+
+/// Smoothly interpolated random values on a grid of `scale` sized cells, in 0..1.
+fn value_noise(seed: u64, x: i32, z: i32, scale: i32) -> f32 {
+    let (cx, cz) = (x.div_euclid(scale), z.div_euclid(scale));
+    let t = |e: i32| {
+        let t = e.rem_euclid(scale) as f32 / scale as f32;
+        t * t * (3.0 - 2.0 * t)
+    };
+    let (tx, tz) = (t(x), t(z));
+
+    let a = hash(seed, cx, cz);
+    let b = hash(seed, cx + 1, cz);
+    let c = hash(seed, cx, cz + 1);
+    let d = hash(seed, cx + 1, cz + 1);
+    let top = a + (b - a) * tx;
+    let bottom = c + (d - c) * tx;
+    top + (bottom - top) * tz
+}
+
+/// The Y of the highest block in the terrain column at `x`, `z`.
+pub fn terrain_height(seed: u64, x: i32, z: i32) -> i32 {
+    let hills = value_noise(seed, x, z, 32) * 12.0;
+    let bumps = value_noise(seed.wrapping_add(1), x, z, 8) * 3.0;
+    BASE_HEIGHT + (hills + bumps - 7.5).round() as i32
+}
+
+pub fn generate_chunk(seed: u64, chunk_pos: Vec3<i32>) -> Chunk {
+    let origin = chunk_origin(chunk_pos);
+    let mut chunk = Chunk::new();
+    for z in 0..CHUNK_SIZE {
+        for x in 0..CHUNK_SIZE {
+            let height = terrain_height(seed, origin.x + x, origin.z + z);
+            for y in 0..CHUNK_SIZE {
+                let world_y = origin.y + y;
+                let block = match height - world_y {
+                    depth if depth < 0 => continue,
+                    0 => Block::GRASS,
+                    1..=3 => Block::DIRT,
+                    _ => Block::STONE,
+                };
+                chunk.set(Vec3::new(x, y, z), block);
+            }
+        }
+    }
+    chunk
+}
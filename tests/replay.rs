@@ -0,0 +1,100 @@
+use wgpu_voxel_game::input::{FrameInput, Key};
+use wgpu_voxel_game::replay::Recording;
+use wgpu_voxel_game::world::World;
+
+fn temp_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("{name}-{}.replay", std::process::id()))
+}
+
+/// Records a session of walking forward for one second.
+fn walk_forward(seed: u64) -> Recording {
+    let mut recording = Recording::new(seed);
+    let mut input = FrameInput::new();
+    input.press_key(Key::W);
+    for _ in 0..60 {
+        recording.record(&input);
+        input.clear();
+    }
+    input.release_key(Key::W);
+    recording.record(&input);
+    recording
+}
+
+#[test]
+fn save_and_load_round_trip() {
+    let recording = walk_forward(42);
+    let path = temp_path("round-trip");
+    recording.save(&path).unwrap();
+    let loaded = Recording::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(recording, loaded);
+}
+
+#[test]
+fn replay_ends_at_expected_position() {
+    let start = World::new(0).player.pos;
+    let world = walk_forward(0).replay();
+
+    assert_eq!(world.tick, 61);
+    // the player starts looking down -Z, and moves 0.1 per tick
+    let pos = world.player.pos;
+    assert!((pos.x - start.x).abs() < 1e-4, "{pos:?}");
+    assert!((pos.y - start.y).abs() < 1e-4, "{pos:?}");
+    assert!((pos.z - (start.z - 6.0)).abs() < 1e-4, "{pos:?}");
+}
+
+#[test]
+fn replay_is_deterministic() {
+    let mut recording = Recording::new(7);
+    let mut input = FrameInput::new();
+    for i in 0..200 {
+        if i % 30 == 0 {
+            input.press_key(Key::W);
+        }
+        if i % 30 == 15 {
+            input.release_key(Key::W);
+            input.press_key(Key::D);
+        }
+        input.cursor_change.x = (i as f64 * 0.37).sin() * 5.0;
+        input.cursor_change.y = (i as f64 * 0.11).cos() * 3.0;
+        recording.record(&input);
+        input.clear();
+    }
+
+    let path = temp_path("deterministic");
+    recording.save(&path).unwrap();
+    let loaded = Recording::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let a = recording.replay();
+    let b = loaded.replay();
+    assert_eq!(a.player.pos, b.player.pos);
+    assert_eq!(a.player.cam.rot, b.player.cam.rot);
+}
+
+#[test]
+fn replay_matches_the_live_session() {
+    let mut live = World::new(3);
+    let mut recording = Recording::new(3);
+    let mut input = FrameInput::new();
+    for i in 0..200 {
+        match i % 40 {
+            0 => input.press_key(Key::W),
+            10 => input.press_key(Key::Space),
+            20 => input.release_key(Key::Space),
+            30 => input.release_key(Key::W),
+            _ => {}
+        }
+        input.cursor_change.x = (i as f64 * 0.23).sin() * 4.0;
+        recording.record(&input);
+        live.tick(&input);
+        input.clear();
+    }
+
+    let replayed = recording.replay();
+    assert_eq!(replayed.tick, live.tick);
+    assert_eq!(replayed.player.pos, live.player.pos);
+    assert_eq!(replayed.player.cam.rot, live.player.cam.rot);
+    assert_eq!(replayed.entities.len(), live.entities.len());
+}